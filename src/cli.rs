@@ -0,0 +1,74 @@
+use crate::entity::mint::MintRequest;
+use crate::service;
+
+pub(crate) const USAGE: &str =
+    "usage: mint <user> [--type access_token|id_token] [--client <client_id>] \
+[--scope <scope>] [--audience <audience>]... [--lifetime <seconds>] [--claim <name>=<value>]... \
+[--tampering <flaw>]";
+
+/// Reads the value of a `--name value` option.
+fn value(name: &str, args: &mut impl Iterator<Item = String>) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {name}\n{USAGE}"))
+}
+
+pub(crate) fn parse(mut args: impl Iterator<Item = String>) -> Result<MintRequest, String> {
+    let mut request = MintRequest::default();
+    let mut user = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--type" => request.token_type = value(&arg, &mut args)?.parse()?,
+            "--client" => request.client_id = Some(value(&arg, &mut args)?),
+            "--scope" => request.scope = Some(value(&arg, &mut args)?),
+            "--audience" => request.audience.push(value(&arg, &mut args)?),
+            "--lifetime" => {
+                let lifetime = value(&arg, &mut args)?;
+                request.lifetime = Some(
+                    lifetime
+                        .parse()
+                        .map_err(|_| format!("invalid lifetime {lifetime:?}"))?,
+                );
+            }
+            "--tampering" => request.tampering = Some(value(&arg, &mut args)?.parse()?),
+            "--claim" => {
+                let claim = value(&arg, &mut args)?;
+                let (name, raw) = claim
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid claim {claim:?}, expected <name>=<value>"))?;
+                // values that aren't valid JSON are taken as strings
+                let value = serde_json::from_str(raw).unwrap_or_else(|_| raw.into());
+                request.claims.insert(name.to_string(), value);
+            }
+            option if option.starts_with("--") => {
+                return Err(format!("unknown option {option:?}\n{USAGE}"))
+            }
+            _ if user.is_none() => user = Some(arg),
+            _ => return Err(format!("unexpected argument {arg:?}\n{USAGE}")),
+        }
+    }
+    request.user = user.ok_or_else(|| format!("missing user\n{USAGE}"))?;
+    Ok(request)
+}
+
+/// Prints a token minted for a configured user, signed with the keys of the configuration.
+pub(crate) fn mint(args: impl Iterator<Item = String>) -> Result<(), String> {
+    let request = parse(args)?;
+    let config = service::Config::from_env();
+    let (host, port) = crate::address_from_env();
+    let base_url = service::baseurl::BaseUrl::from_env_or_new(host, port, config.tls.is_some());
+    let clock = config.deterministic.as_ref().map_or_else(
+        service::clock::Clock::default,
+        service::random::Config::clock,
+    );
+    let random = service::random::Random::from(config.deterministic.as_ref());
+    let response = crate::handler::admin::tokens::encode(
+        &service::jsonwebtoken::JsonWebToken::new(config.jsonwebtoken, clock, random),
+        &service::oauth::Oauth::from(config.oauth),
+        &service::database::DatabaseUser::from(config.users),
+        base_url.as_ref(),
+        request,
+    )
+    .map_err(|err| err.error_description.into_owned())?;
+    println!("{}", response.token);
+    Ok(())
+}
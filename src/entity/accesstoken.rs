@@ -1,14 +1,99 @@
+use uuid::Uuid;
+
+use crate::entity::authorization::{
+    deserialize_authorization_details, deserialize_one_or_many, AuthorizationDetail,
+};
+use crate::service::jsonwebtoken::Actor;
+
+/// Token types supported by the token exchange, as defined by RFC 8693.
+pub(crate) const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+pub(crate) const JWT_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:jwt";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GrantType {
+    #[default]
+    AuthorizationCode,
+    RefreshToken,
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:token-exchange")]
+    TokenExchange,
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:jwt-bearer")]
+    JwtBearer,
+    #[serde(rename = "urn:openid:params:grant-type:ciba")]
+    Ciba,
+    #[serde(other)]
+    Unsupported,
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub(crate) struct AccessTokenRequest {
-    pub code: String,
-    // pub code_verifier: String,
-    // pub grant_type: String,
-    pub redirect_uri: String,
+    /// Token of the party acting on behalf of the subject, for a delegation.
+    pub actor_token: Option<String>,
+    pub actor_token_type: Option<String>,
+    /// Identifier of the backchannel authentication, for the CIBA grant.
+    pub auth_req_id: Option<String>,
+    /// JWT signed by a trusted issuer, for the JWT bearer grant.
+    pub assertion: Option<String>,
+    /// Logical names of the services the exchanged token is intended for.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub audience: Vec<String>,
+    /// Subset of the authorization details granted with the code.
+    #[serde(default, deserialize_with = "deserialize_authorization_details")]
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
+    /// Identifies the client when it doesn't authenticate with HTTP Basic.
+    pub client_id: Option<String>,
+    pub code: Option<String>,
+    pub code_verifier: Option<String>,
+    #[serde(default)]
+    pub grant_type: GrantType,
+    pub redirect_uri: Option<String>,
+    pub refresh_token: Option<String>,
+    /// Subset of the granted resources the access token is restricted to, as defined by RFC 8707.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub resource: Vec<String>,
+    pub requested_token_type: Option<String>,
+    /// Subset of the granted scopes.
+    pub scope: Option<String>,
+    /// Token representing the user on whose behalf the exchanged token is requested.
+    pub subject_token: Option<String>,
+    pub subject_token_type: Option<String>,
+}
+
+impl AccessTokenRequest {
+    /// Resources and audiences the access token is requested for.
+    pub fn targets(&self) -> Vec<String> {
+        let mut res = self.resource.clone();
+        res.extend(self.audience.iter().cloned());
+        res
+    }
+}
+
+/// Authorization granted by the user to the client, from which the access tokens are issued.
+#[derive(Clone, Debug)]
+pub(crate) struct Grant {
+    /// Party acting on behalf of the user, for a delegation.
+    pub act: Option<Actor>,
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
+    /// Client the grant was issued to.
+    pub client_id: String,
+    pub resource: Vec<String>,
+    pub scope: Option<String>,
+    pub user_id: Uuid,
+    /// Time the user authenticated, as UTC timestamp, unknown for the exchanged tokens.
+    pub auth_time: Option<u64>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(Debug, serde::Serialize)]
 pub(crate) struct AccessTokenResponse {
     pub access_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issued_token_type: Option<&'static str>,
     pub token_type: &'static str,
     pub expires_in: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
 }
@@ -1,44 +1,310 @@
 use std::borrow::Cow;
 
+use serde::Deserialize;
 use uuid::Uuid;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ResponseType {
+    pub code: bool,
+    pub token: bool,
+    pub id_token: bool,
+}
+
+impl TryFrom<String> for ResponseType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut res = Self::default();
+        for item in value.split_whitespace() {
+            match item {
+                "code" => res.code = true,
+                "token" => res.token = true,
+                "id_token" => res.id_token = true,
+                other => return Err(format!("unknown response type {other:?}")),
+            }
+        }
+        if res == Self::default() {
+            return Err(String::from("empty response type"));
+        }
+        Ok(res)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ResponseType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for ResponseType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl std::fmt::Display for ResponseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = [
+            (self.code, "code"),
+            (self.id_token, "id_token"),
+            (self.token, "token"),
+        ];
+        let items = items
+            .into_iter()
+            .filter_map(|(enabled, name)| enabled.then_some(name))
+            .collect::<Vec<_>>();
+        write!(f, "{}", items.join(" "))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ResponseMode {
+    Query,
+    Fragment,
+    FormPost,
+    WebMessage,
+    Jwt,
+    #[serde(rename = "query.jwt")]
+    QueryJwt,
+    #[serde(rename = "fragment.jwt")]
+    FragmentJwt,
+    #[serde(rename = "form_post.jwt")]
+    FormPostJwt,
+}
+
+impl ResponseMode {
+    pub const ALL: [Self; 8] = [
+        Self::Query,
+        Self::Fragment,
+        Self::FormPost,
+        Self::WebMessage,
+        Self::Jwt,
+        Self::QueryJwt,
+        Self::FragmentJwt,
+        Self::FormPostJwt,
+    ];
+
+    /// Whether the response has to be wrapped in a signed JWT.
+    pub fn is_jwt(&self) -> bool {
+        matches!(
+            self,
+            Self::Jwt | Self::QueryJwt | Self::FragmentJwt | Self::FormPostJwt
+        )
+    }
+
+    /// Mode used to transmit the response, once wrapped in a JWT when needed.
+    pub fn transport(&self) -> Self {
+        match self {
+            Self::QueryJwt => Self::Query,
+            Self::FragmentJwt => Self::Fragment,
+            Self::FormPostJwt => Self::FormPost,
+            other => *other,
+        }
+    }
+}
+
+/// Fine-grained authorization requested by the client, as defined by RFC 9396.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct AuthorizationDetail {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Reads the `authorization_details` parameter, that is a JSON encoded string
+/// in a query or a form, and a plain array in a request object or a JSON body.
+pub(crate) fn deserialize_authorization_details<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<AuthorizationDetail>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Encoded(String),
+        Parsed(Vec<AuthorizationDetail>),
+    }
+
+    match Option::<Raw>::deserialize(deserializer)? {
+        Some(Raw::Encoded(value)) => serde_json::from_str(&value)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        Some(Raw::Parsed(value)) => Ok(Some(value)),
+        None => Ok(None),
+    }
+}
+
+/// Reads a parameter that can be provided once, as a string, or several times, as an array.
+pub(crate) fn deserialize_one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<Raw>::deserialize(deserializer)? {
+        Some(Raw::One(value)) => vec![value],
+        Some(Raw::Many(values)) => values,
+        None => Vec::new(),
+    })
+}
+
 #[derive(Clone, serde::Deserialize)]
 pub(crate) struct AuthorizationRequest {
+    #[serde(default, deserialize_with = "deserialize_authorization_details")]
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
     pub client_id: String,
-    pub code_challenge: String,
-    // pub code_challenge_method: String,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub nonce: Option<String>,
     pub redirect_uri: String,
-    // pub response_type: String,
+    /// Resources the client wants to access, as defined by RFC 8707.
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub resource: Vec<String>,
+    pub response_mode: Option<ResponseMode>,
+    #[serde(default = "AuthorizationRequest::default_response_type")]
+    pub response_type: ResponseType,
+    pub scope: Option<String>,
+    pub prompt: Option<String>,
     pub state: String,
 }
 
+impl AuthorizationRequest {
+    fn default_response_type() -> ResponseType {
+        ResponseType {
+            code: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn authorization_details_types(&self) -> impl Iterator<Item = &str> {
+        self.authorization_details
+            .iter()
+            .flatten()
+            .map(|detail| detail.kind.as_str())
+    }
+
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.iter().flat_map(|scope| scope.split_whitespace())
+    }
+
+    pub fn has_prompt(&self, value: &str) -> bool {
+        self.prompt
+            .iter()
+            .flat_map(|prompt| prompt.split_whitespace())
+            .any(|item| item == value)
+    }
+
+    /// Response mode requested by the client or the default one for the response type,
+    /// as defined in the OAuth 2.0 Multiple Response Type Encoding Practices and JARM.
+    pub fn response_mode(&self) -> ResponseMode {
+        let with_token = self.response_type.token || self.response_type.id_token;
+        match self.response_mode {
+            Some(ResponseMode::Jwt) if with_token => ResponseMode::FragmentJwt,
+            Some(ResponseMode::Jwt) => ResponseMode::QueryJwt,
+            Some(other) => other,
+            None if with_token => ResponseMode::Fragment,
+            None => ResponseMode::Query,
+        }
+    }
+}
+
+/// Authorization request passed by reference, either as a request object (JAR)
+/// or through a `request_uri` (pushed or hosted by the client).
+#[derive(serde::Deserialize)]
+pub(crate) struct AuthorizationReference {
+    pub client_id: String,
+    pub request: Option<String>,
+    pub request_uri: Option<String>,
+}
+
+impl AuthorizationReference {
+    /// Prefix of the `request_uri` generated for the pushed authorization requests.
+    pub const PUSHED_REQUEST_URI_PREFIX: &'static str = "urn:ietf:params:oauth:request_uri:";
+
+    pub fn is_reference(&self) -> bool {
+        self.request.is_some() || self.request_uri.is_some()
+    }
+}
+
+#[derive(serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct PushedAuthorizationResponse {
+    pub request_uri: String,
+    pub expires_in: u64,
+}
+
 #[derive(Clone)]
 pub(crate) struct AuthorizationResponse {
-    // pub client_id: String,
-    pub code_challenge: String,
-    // pub code_challenge_method: String,
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
+    pub client_id: String,
+    /// Random code, the code challenge being optional for the implicit and hybrid flows.
+    pub code: String,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
     // pub redirect_uri: String,
+    pub resource: Vec<String>,
     // pub response_type: String,
+    pub scope: Option<String>,
     pub state: String,
     //
     pub user_id: Uuid,
+    /// Time the user was selected, as UTC timestamp.
+    pub auth_time: u64,
 }
 
-#[derive(Debug, serde::Serialize)]
+impl AuthorizationResponse {
+    /// Checks the PKCE code verifier against the challenge sent with the authorization request.
+    pub fn verify_code_challenge(&self, verifier: Option<&str>) -> bool {
+        use base64::Engine;
+        use sha2::Digest;
+
+        match (self.code_challenge.as_deref(), verifier) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(challenge), Some(verifier)) => match self.code_challenge_method.as_deref() {
+                Some("S256") => {
+                    let digest = sha2::Sha256::digest(verifier.as_bytes());
+                    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest) == challenge
+                }
+                None | Some("plain") => verifier == challenge,
+                Some(_) => false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize)]
 #[cfg_attr(test, derive(serde::Deserialize))]
 pub(crate) struct AuthorizationRedirect {
-    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub access_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
     pub state: String,
 }
 
 impl AuthorizationRedirect {
     #[inline]
-    pub fn new(code: String, state: String) -> Self {
-        Self { code, state }
-    }
-
-    pub fn as_redirect_url(&self, url: &str) -> String {
-        format!("{url}?{}", serde_qs::to_string(&self).unwrap())
+    pub fn new(state: String) -> Self {
+        Self {
+            state,
+            ..Default::default()
+        }
     }
 }
 
@@ -48,9 +314,3 @@ pub(crate) struct AuthorizationError {
     pub error_description: Cow<'static, str>,
     pub state: Option<String>,
 }
-
-impl AuthorizationError {
-    pub fn as_redirect_url(&self, url: &str) -> String {
-        format!("{url}?{}", serde_qs::to_string(&self).unwrap())
-    }
-}
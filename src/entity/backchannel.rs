@@ -0,0 +1,92 @@
+use std::time::SystemTime;
+
+use uuid::Uuid;
+
+use crate::entity::accesstoken::AccessTokenResponse;
+
+/// How the tokens of a backchannel authentication are delivered to the client, as defined by CIBA.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BackchannelTokenDeliveryMode {
+    /// The client polls the token endpoint until the user decides.
+    Poll,
+    /// The client is notified once the user decided, then calls the token endpoint.
+    Ping,
+    /// The tokens are sent to the client notification endpoint.
+    Push,
+}
+
+impl BackchannelTokenDeliveryMode {
+    /// Whether the client is called back on its notification endpoint.
+    pub fn notifies(&self) -> bool {
+        !matches!(self, Self::Poll)
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct BackchannelAuthenticationRequest {
+    /// Message displayed to the user, that the client also shows on its own device.
+    pub binding_message: Option<String>,
+    /// Identifies the client when it doesn't authenticate with HTTP Basic.
+    pub client_id: Option<String>,
+    /// Bearer token used to call the client notification endpoint, for the ping and push modes.
+    pub client_notification_token: Option<String>,
+    /// Id or email of the user to authenticate.
+    pub login_hint: Option<String>,
+    /// Lifetime of the request requested by the client, in seconds.
+    pub requested_expiry: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct BackchannelAuthenticationResponse {
+    pub auth_req_id: String,
+    pub expires_in: u64,
+    /// Minimum delay between two polls of the token endpoint, in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum BackchannelStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+/// Backchannel authentication waiting for, or holding, the decision of the user.
+#[derive(Clone, Debug)]
+pub(crate) struct BackchannelAuthentication {
+    pub auth_req_id: String,
+    pub binding_message: Option<String>,
+    /// Client that started the authentication.
+    pub client_id: String,
+    pub client_notification_token: Option<String>,
+    pub delivery_mode: BackchannelTokenDeliveryMode,
+    pub expires_at: SystemTime,
+    /// Time of the latest poll of the token endpoint, to make the client slow down.
+    pub last_polled_at: Option<SystemTime>,
+    pub scope: Option<String>,
+    pub status: BackchannelStatus,
+    pub user_id: Uuid,
+    /// Time the user decided, as UTC timestamp.
+    pub auth_time: Option<u64>,
+}
+
+impl BackchannelAuthentication {
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Notification sent to the client once the user decided, with the tokens in push mode.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct BackchannelNotification {
+    pub auth_req_id: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub token: Option<AccessTokenResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<&'static str>,
+}
@@ -0,0 +1,33 @@
+use uuid::Uuid;
+
+use crate::service::jsonwebtoken::{Actor, Confirmation};
+
+/// The `token_type_hint` is ignored, every kind of access token is looked up.
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct IntrospectionRequest {
+    pub token: String,
+    /// Identifies the client when it doesn't authenticate with HTTP Basic.
+    pub client_id: Option<String>,
+}
+
+/// State of a token, as defined by RFC 7662.
+#[derive(Debug, Default, serde::Serialize)]
+pub(crate) struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aud: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<Confirmation>,
+}
@@ -0,0 +1,56 @@
+use crate::entity::authorization::deserialize_one_or_many;
+use crate::service::jsonwebtoken::Tampering;
+
+/// Kind of token minted without going through an authorization flow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum MintedTokenType {
+    #[default]
+    #[serde(rename = "access_token")]
+    Access,
+    #[serde(rename = "id_token")]
+    Id,
+    #[serde(rename = "refresh_token")]
+    Refresh,
+}
+
+impl std::str::FromStr for MintedTokenType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "access_token" => Ok(Self::Access),
+            "id_token" => Ok(Self::Id),
+            "refresh_token" => Ok(Self::Refresh),
+            other => Err(format!("unknown token type {other:?}")),
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub(crate) struct MintRequest {
+    #[serde(default)]
+    pub token_type: MintedTokenType,
+    /// Id or email of the user the token is issued for.
+    pub user: String,
+    /// Client the token is issued to, required for the ID and refresh tokens.
+    pub client_id: Option<String>,
+    pub scope: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub audience: Vec<String>,
+    /// Lifetime of the token in seconds, instead of the configured one.
+    pub lifetime: Option<u64>,
+    /// Additional claims added to the access or ID token.
+    #[serde(default)]
+    pub claims: serde_json::Map<String, serde_json::Value>,
+    /// Flaw put in the access or ID token.
+    pub tampering: Option<Tampering>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct MintResponse {
+    pub token: String,
+    pub token_type: MintedTokenType,
+    /// Expiration time of the token, as UTC timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
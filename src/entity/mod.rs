@@ -1,3 +1,7 @@
 pub(crate) mod accesstoken;
 pub(crate) mod authorization;
+pub(crate) mod backchannel;
+pub(crate) mod introspection;
+pub(crate) mod mint;
+pub(crate) mod registration;
 pub(crate) mod user;
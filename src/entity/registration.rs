@@ -0,0 +1,41 @@
+use crate::entity::authorization::ResponseType;
+use crate::service::oauth::TokenEndpointAuthMethod;
+
+/// Metadata of a client registering itself, as defined by RFC 7591.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ClientMetadata {
+    pub redirect_uris: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(default)]
+    pub token_endpoint_auth_method: TokenEndpointAuthMethod,
+    /// Response types the client will use, `code` only by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_types: Option<Vec<ResponseType>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwks: Option<jsonwebtoken::jwk::JwkSet>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_subject_dn: Option<String>,
+    #[serde(default)]
+    pub tls_client_certificate_bound_access_tokens: bool,
+    #[serde(default)]
+    pub dpop_bound_access_tokens: bool,
+    /// URLs the request objects can be fetched from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_uris: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_signed_response_alg: Option<jsonwebtoken::Algorithm>,
+}
+
+/// Registration of a client, returned on registration and by the management endpoint.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ClientInformation {
+    pub client_id: String,
+    pub client_secret: String,
+    /// The secrets never expire.
+    pub client_secret_expires_at: u64,
+    pub registration_access_token: String,
+    pub registration_client_uri: String,
+    #[serde(flatten)]
+    pub metadata: ClientMetadata,
+}
@@ -0,0 +1,85 @@
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::chaos::{Chaos, ChaosScenario};
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+fn scenario_not_found() -> ApiError {
+    ApiError::not_found(AuthorizationError {
+        error: "not_found".into(),
+        error_description: "Unable to find the chaos scenario.".into(),
+        state: None,
+    })
+}
+
+pub(crate) async fn list(
+    _: AdminAccess,
+    Extension(chaos): Extension<Chaos>,
+) -> Json<Vec<ChaosScenario>> {
+    Json(chaos.list())
+}
+
+/// Adds or replaces the scenario with this name.
+pub(crate) async fn put(
+    _: AdminAccess,
+    Extension(chaos): Extension<Chaos>,
+    Path(name): Path<String>,
+    Json(scenario): Json<ChaosScenario>,
+) -> Result<Json<ChaosScenario>, ApiError> {
+    if scenario.name != name {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: "The name of the scenario doesn't match the path.".into(),
+            state: None,
+        }));
+    }
+    scenario.check().map_err(|message| {
+        ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: message.into(),
+            state: None,
+        })
+    })?;
+    chaos.put(scenario.clone());
+    Ok(Json(scenario))
+}
+
+pub(crate) async fn enable(
+    _: AdminAccess,
+    Extension(chaos): Extension<Chaos>,
+    Path(name): Path<String>,
+) -> Result<Json<ChaosScenario>, ApiError> {
+    chaos
+        .set_enabled(&name, true)
+        .map(Json)
+        .ok_or_else(scenario_not_found)
+}
+
+pub(crate) async fn disable(
+    _: AdminAccess,
+    Extension(chaos): Extension<Chaos>,
+    Path(name): Path<String>,
+) -> Result<Json<ChaosScenario>, ApiError> {
+    chaos
+        .set_enabled(&name, false)
+        .map(Json)
+        .ok_or_else(scenario_not_found)
+}
+
+pub(crate) async fn delete(
+    _: AdminAccess,
+    Extension(chaos): Extension<Chaos>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    chaos
+        .remove(&name)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(scenario_not_found)
+}
+
+pub(crate) async fn clear(_: AdminAccess, Extension(chaos): Extension<Chaos>) -> StatusCode {
+    chaos.clear();
+    StatusCode::NO_CONTENT
+}
@@ -0,0 +1,130 @@
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::jsonwebtoken::JsonWebToken;
+use crate::service::oauth::{Client, Config, Oauth};
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+/// Registration of a client, without its secrets.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ClientSummary {
+    pub client_id: String,
+    pub client_name: String,
+    pub redirect_uri: Vec<String>,
+}
+
+impl From<Client> for ClientSummary {
+    fn from(value: Client) -> Self {
+        Self {
+            client_id: value.client_id().to_string(),
+            client_name: value.client_name().to_string(),
+            redirect_uri: value.redirect_uris().to_vec(),
+        }
+    }
+}
+
+fn client_not_found() -> ApiError {
+    ApiError::not_found(AuthorizationError {
+        error: "invalid_client_id".into(),
+        error_description: "Unable to find an application with the provided client_id.".into(),
+        state: None,
+    })
+}
+
+fn validate(jwt: &JsonWebToken, config: &Config) -> Result<(), ApiError> {
+    if config.client_id.trim().is_empty() || config.redirect_uri.is_empty() {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_client_metadata".into(),
+            error_description: "The client requires a client_id and a redirect_uri.".into(),
+            state: None,
+        }));
+    }
+    if !jwt.can_sign_response(config.authorization_signed_response_alg) {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_client_metadata".into(),
+            error_description: format!(
+                "The authorization responses cannot be signed with {:?}.",
+                config.authorization_signed_response_alg
+            )
+            .into(),
+            state: None,
+        }));
+    }
+    Ok(())
+}
+
+pub(crate) async fn list(
+    _: AdminAccess,
+    Extension(oauth): Extension<Oauth>,
+) -> Json<Vec<ClientSummary>> {
+    Json(oauth.list().into_iter().map(ClientSummary::from).collect())
+}
+
+pub(crate) async fn get(
+    _: AdminAccess,
+    Extension(oauth): Extension<Oauth>,
+    Path(client_id): Path<String>,
+) -> Result<Json<ClientSummary>, ApiError> {
+    oauth
+        .client(&client_id)
+        .map(|client| Json(ClientSummary::from(client)))
+        .map_err(|_| client_not_found())
+}
+
+pub(crate) async fn create(
+    _: AdminAccess,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(oauth): Extension<Oauth>,
+    Json(config): Json<Config>,
+) -> Result<(StatusCode, Json<ClientSummary>), ApiError> {
+    validate(&jwt, &config)?;
+    if oauth.client(&config.client_id).is_ok() {
+        return Err(ApiError::conflict(AuthorizationError {
+            error: "client_exists".into(),
+            error_description: "An application already has this client_id.".into(),
+            state: None,
+        }));
+    }
+    let client_id = config.client_id.clone();
+    oauth.register(config);
+    let client = oauth.client(&client_id).map_err(|_| client_not_found())?;
+    Ok((StatusCode::CREATED, Json(ClientSummary::from(client))))
+}
+
+pub(crate) async fn update(
+    _: AdminAccess,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(oauth): Extension<Oauth>,
+    Path(client_id): Path<String>,
+    Json(config): Json<Config>,
+) -> Result<Json<ClientSummary>, ApiError> {
+    validate(&jwt, &config)?;
+    if config.client_id != client_id {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_client_metadata".into(),
+            error_description: "The client_id cannot be changed.".into(),
+            state: None,
+        }));
+    }
+    let existing = oauth.client(&client_id).map_err(|_| client_not_found())?;
+    // the dynamically registered clients keep managing their registration
+    oauth.register(Config {
+        registration_access_token: existing.registration_access_token().map(String::from),
+        ..config
+    });
+    let client = oauth.client(&client_id).map_err(|_| client_not_found())?;
+    Ok(Json(ClientSummary::from(client)))
+}
+
+pub(crate) async fn delete(
+    _: AdminAccess,
+    Extension(oauth): Extension<Oauth>,
+    Path(client_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    oauth
+        .remove(&client_id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(client_not_found)
+}
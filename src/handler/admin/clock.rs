@@ -0,0 +1,82 @@
+use std::time::{Duration, SystemTime};
+
+use axum::{http::StatusCode, Extension, Json};
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::clock::{Clock, ClockStatus};
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+fn out_of_range() -> ApiError {
+    ApiError::bad_request(AuthorizationError {
+        error: "invalid_request".into(),
+        error_description: "The time is out of range.".into(),
+        state: None,
+    })
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct SetClockRequest {
+    /// New time of the clock, as UTC timestamp.
+    pub now: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct AdvanceClockRequest {
+    pub seconds: u64,
+}
+
+pub(crate) async fn status(
+    _: AdminAccess,
+    Extension(clock): Extension<Clock>,
+) -> Json<ClockStatus> {
+    Json(clock.status())
+}
+
+pub(crate) async fn set(
+    _: AdminAccess,
+    Extension(clock): Extension<Clock>,
+    Json(payload): Json<SetClockRequest>,
+) -> Result<Json<ClockStatus>, ApiError> {
+    let now = SystemTime::UNIX_EPOCH
+        .checked_add(Duration::from_secs(payload.now))
+        .ok_or_else(out_of_range)?;
+    if !clock.set(now) {
+        return Err(out_of_range());
+    }
+    Ok(Json(clock.status()))
+}
+
+pub(crate) async fn freeze(
+    _: AdminAccess,
+    Extension(clock): Extension<Clock>,
+) -> Json<ClockStatus> {
+    clock.freeze();
+    Json(clock.status())
+}
+
+pub(crate) async fn unfreeze(
+    _: AdminAccess,
+    Extension(clock): Extension<Clock>,
+) -> Json<ClockStatus> {
+    clock.unfreeze();
+    Json(clock.status())
+}
+
+pub(crate) async fn advance(
+    _: AdminAccess,
+    Extension(clock): Extension<Clock>,
+    Json(payload): Json<AdvanceClockRequest>,
+) -> Result<Json<ClockStatus>, ApiError> {
+    if !clock.advance(Duration::from_secs(payload.seconds)) {
+        return Err(out_of_range());
+    }
+    Ok(Json(clock.status()))
+}
+
+/// Goes back to the wall-clock time.
+pub(crate) async fn reset(_: AdminAccess, Extension(clock): Extension<Clock>) -> StatusCode {
+    clock.reset();
+    StatusCode::NO_CONTENT
+}
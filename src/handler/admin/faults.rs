@@ -0,0 +1,52 @@
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use uuid::Uuid;
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::fault::{FaultRule, Faults};
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+pub(crate) async fn list(
+    _: AdminAccess,
+    Extension(faults): Extension<Faults>,
+) -> Json<Vec<FaultRule>> {
+    Json(faults.list())
+}
+
+pub(crate) async fn create(
+    _: AdminAccess,
+    Extension(faults): Extension<Faults>,
+    Json(rule): Json<FaultRule>,
+) -> Result<(StatusCode, Json<FaultRule>), ApiError> {
+    if !rule.path.starts_with('/') {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: "The path of the endpoint must start with a slash.".into(),
+            state: None,
+        }));
+    }
+    Ok((StatusCode::CREATED, Json(faults.add(rule))))
+}
+
+pub(crate) async fn delete(
+    _: AdminAccess,
+    Extension(faults): Extension<Faults>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    faults
+        .remove(id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(|| {
+            ApiError::not_found(AuthorizationError {
+                error: "not_found".into(),
+                error_description: "Unable to find the fault injection rule.".into(),
+                state: None,
+            })
+        })
+}
+
+pub(crate) async fn clear(_: AdminAccess, Extension(faults): Extension<Faults>) -> StatusCode {
+    faults.clear();
+    StatusCode::NO_CONTENT
+}
@@ -0,0 +1,19 @@
+use axum::{extract::Query, http::StatusCode, Extension, Json};
+
+use crate::service::journal::{Journal, JournalEntry, JournalFilter};
+
+use super::AdminAccess;
+
+pub(crate) async fn list(
+    _: AdminAccess,
+    Extension(journal): Extension<Journal>,
+    Query(filter): Query<JournalFilter>,
+) -> Json<Vec<JournalEntry>> {
+    Json(journal.list(&filter))
+}
+
+/// Forgets the recorded requests, typically between two tests.
+pub(crate) async fn clear(_: AdminAccess, Extension(journal): Extension<Journal>) -> StatusCode {
+    journal.clear();
+    StatusCode::NO_CONTENT
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{header::AUTHORIZATION, request::Parts},
+    Extension,
+};
+use axum_extra::headers::{Cookie, HeaderMapExt};
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::admin::Admin;
+
+use super::ApiError;
+
+pub(crate) mod chaos;
+pub(crate) mod clients;
+pub(crate) mod clock;
+pub(crate) mod faults;
+pub(crate) mod journal;
+pub(crate) mod personaltokens;
+pub(crate) mod tokens;
+pub(crate) mod users;
+
+/// Cookie carrying the admin token, for the pages used in a browser.
+pub(crate) const ADMIN_TOKEN_COOKIE: &str = "admin_token";
+/// Query parameter carrying the admin token, when opening such a page.
+pub(crate) const ADMIN_TOKEN_PARAM: &str = "admin_token";
+
+/// Admin token given in the query parameters.
+fn query_token(parts: &Parts) -> Option<String> {
+    let Query(params) = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri).ok()?;
+    params
+        .into_iter()
+        .find(|(key, _)| key == ADMIN_TOKEN_PARAM)
+        .map(|(_, value)| value)
+}
+
+/// Proof that the request carries the admin token, as bearer token, cookie or query parameter.
+pub(crate) struct AdminAccess;
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for AdminAccess
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(admin) = Extension::<Admin>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| {
+                ApiError::internal(AuthorizationError {
+                    error: "server_error".into(),
+                    error_description: "The admin service is not configured.".into(),
+                    state: None,
+                })
+            })?;
+        if !admin.is_enabled() {
+            return Err(ApiError::not_found(AuthorizationError {
+                error: "not_found".into(),
+                error_description: "The admin API is disabled.".into(),
+                state: None,
+            }));
+        }
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(String::from)
+            .or_else(|| {
+                let cookie = parts.headers.typed_get::<Cookie>()?;
+                cookie.get(ADMIN_TOKEN_COOKIE).map(String::from)
+            })
+            .or_else(|| query_token(parts));
+        if !token.is_some_and(|token| admin.authorize(token.trim())) {
+            return Err(ApiError::unauthorized(AuthorizationError {
+                error: "invalid_token".into(),
+                error_description: "The admin token is missing or invalid.".into(),
+                state: None,
+            })
+            .with_header("www-authenticate", "Bearer"));
+        }
+        Ok(Self)
+    }
+}
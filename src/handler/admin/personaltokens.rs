@@ -0,0 +1,116 @@
+use std::time::{Duration, SystemTime};
+
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use uuid::Uuid;
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::database::DatabaseUser;
+use crate::service::opaquetoken::{OpaqueToken, OpaqueTokens};
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct PersonalTokenPayload {
+    pub name: String,
+    /// Id or email of the user the token acts for.
+    pub user: String,
+    pub scope: Option<String>,
+    /// Lifetime of the token in seconds, the token never expires without it.
+    pub expires_in: Option<u64>,
+}
+
+/// Personal access token, with its value only when it is created.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct PersonalTokenSummary {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub user_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Expiration time of the token, as UTC timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl From<OpaqueToken> for PersonalTokenSummary {
+    fn from(value: OpaqueToken) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            user_id: value.user_id,
+            scope: value.scope,
+            expires_at: value.expires_at.map(|expires_at| {
+                expires_at
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            }),
+            token: None,
+        }
+    }
+}
+
+pub(crate) async fn list(
+    _: AdminAccess,
+    Extension(tokens): Extension<OpaqueTokens>,
+) -> Json<Vec<PersonalTokenSummary>> {
+    Json(
+        tokens
+            .personal()
+            .into_iter()
+            .map(PersonalTokenSummary::from)
+            .collect(),
+    )
+}
+
+pub(crate) async fn create(
+    _: AdminAccess,
+    Extension(database): Extension<DatabaseUser>,
+    Extension(tokens): Extension<OpaqueTokens>,
+    Json(payload): Json<PersonalTokenPayload>,
+) -> Result<(StatusCode, Json<PersonalTokenSummary>), ApiError> {
+    let invalid_request = |description: &'static str| {
+        ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: description.into(),
+            state: None,
+        })
+    };
+    if payload.name.trim().is_empty() {
+        return Err(invalid_request("The token requires a name."));
+    }
+    let user = database
+        .find(&payload.user)
+        .ok_or_else(|| invalid_request("Unable to find user."))?;
+    let (value, token) = tokens
+        .create(
+            payload.name,
+            user.id,
+            payload.scope,
+            payload.expires_in.map(Duration::from_secs),
+        )
+        .ok_or_else(|| invalid_request("The expires_in is too large."))?;
+    let mut summary = PersonalTokenSummary::from(token);
+    summary.token = Some(value);
+    Ok((StatusCode::CREATED, Json(summary)))
+}
+
+pub(crate) async fn revoke(
+    _: AdminAccess,
+    Extension(tokens): Extension<OpaqueTokens>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    tokens
+        .revoke(id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(|| {
+            ApiError::not_found(AuthorizationError {
+                error: "not_found".into(),
+                error_description: "Unable to find the personal access token.".into(),
+                state: None,
+            })
+        })
+}
@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use axum::{Extension, Json};
+use uuid::Uuid;
+
+use crate::entity::accesstoken::Grant;
+use crate::entity::authorization::AuthorizationError;
+use crate::entity::mint::{MintRequest, MintResponse, MintedTokenType};
+use crate::service::baseurl::BaseUrl;
+use crate::service::cache::Cache;
+use crate::service::database::DatabaseUser;
+use crate::service::jsonwebtoken::{AccessTokenParams, IdTokenParams, JsonWebToken};
+use crate::service::oauth::Oauth;
+use crate::service::random::Random;
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+/// Claims set by the server, that the additional claims can't override.
+const RESERVED_CLAIMS: &[&str] = &[
+    "iss",
+    "sub",
+    "aud",
+    "exp",
+    "iat",
+    "jti",
+    "act",
+    "cnf",
+    "authorization_details",
+    "nonce",
+    "at_hash",
+    "c_hash",
+];
+
+fn invalid_request(description: impl Into<String>) -> AuthorizationError {
+    AuthorizationError {
+        error: "invalid_request".into(),
+        error_description: description.into().into(),
+        state: None,
+    }
+}
+
+/// Checks the request, returning the id of the user and the client.
+fn validate(
+    oauth: &Oauth,
+    database: &DatabaseUser,
+    request: &MintRequest,
+) -> Result<(Uuid, Option<String>), AuthorizationError> {
+    let user = database
+        .find(&request.user)
+        .ok_or_else(|| invalid_request(format!("Unable to find the user {:?}.", request.user)))?;
+    let client_id = match request.client_id.as_deref() {
+        Some(client_id) => Some(oauth.client(client_id)?.client_id().to_string()),
+        None => None,
+    };
+    if let Some(claim) = request
+        .claims
+        .keys()
+        .find(|claim| RESERVED_CLAIMS.contains(&claim.as_str()))
+    {
+        return Err(invalid_request(format!(
+            "The claim {claim:?} is set by the server."
+        )));
+    }
+    match request.token_type {
+        MintedTokenType::Access => {}
+        MintedTokenType::Id if client_id.is_none() => {
+            return Err(invalid_request("An ID token requires a client_id."));
+        }
+        MintedTokenType::Id if !request.audience.is_empty() => {
+            return Err(invalid_request(
+                "The audience of an ID token is its client.",
+            ));
+        }
+        MintedTokenType::Id => {}
+        MintedTokenType::Refresh if client_id.is_none() => {
+            return Err(invalid_request("A refresh token requires a client_id."));
+        }
+        MintedTokenType::Refresh if request.lifetime.is_some() => {
+            return Err(invalid_request(
+                "The lifetime of a refresh token can't be changed.",
+            ));
+        }
+        MintedTokenType::Refresh if request.tampering.is_some() => {
+            return Err(invalid_request("A refresh token can't be tampered with."));
+        }
+        MintedTokenType::Refresh if !request.claims.is_empty() => {
+            return Err(invalid_request("A refresh token can't hold claims."));
+        }
+        MintedTokenType::Refresh => {}
+    }
+    Ok((user.id, client_id))
+}
+
+/// Signs an access or ID token for the user, shared with the `mint` command.
+pub(crate) fn encode(
+    jwt: &JsonWebToken,
+    oauth: &Oauth,
+    database: &DatabaseUser,
+    issuer: &str,
+    request: MintRequest,
+) -> Result<MintResponse, AuthorizationError> {
+    let (user_id, client_id) = validate(oauth, database, &request)?;
+    let lifetime = request.lifetime.map(Duration::from_secs);
+    if lifetime.is_some_and(|lifetime| !jwt.accepts_lifetime(lifetime)) {
+        return Err(invalid_request("The lifetime is too large."));
+    }
+    let mut claims = request.claims;
+    match request.token_type {
+        MintedTokenType::Access => {
+            if let Some(scope) = request.scope {
+                claims.insert("scope".into(), scope.into());
+            }
+            if let Some(client_id) = client_id {
+                claims.insert("client_id".into(), client_id.into());
+            }
+            let (token, expires_at) = jwt.encode(AccessTokenParams {
+                user_id,
+                cnf: None,
+                audience: request.audience,
+                act: None,
+                authorization_details: None,
+                auth_time: None,
+                lifetime,
+                claims,
+                tampering: request.tampering,
+            });
+            Ok(MintResponse {
+                token,
+                token_type: MintedTokenType::Access,
+                expires_at: Some(expires_at),
+            })
+        }
+        MintedTokenType::Id => {
+            let token = jwt.encode_id_token(IdTokenParams {
+                issuer,
+                client_id: client_id.as_deref().unwrap_or_default(),
+                user_id,
+                auth_time: None,
+                nonce: None,
+                access_token: None,
+                code: None,
+                lifetime,
+                claims,
+                tampering: request.tampering,
+            });
+            Ok(MintResponse {
+                token,
+                token_type: MintedTokenType::Id,
+                expires_at: None,
+            })
+        }
+        MintedTokenType::Refresh => Err(invalid_request(
+            "Refresh tokens can only be minted by a running server.",
+        )),
+    }
+}
+
+/// Mints a token for a configured user, without going through an authorization flow.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn mint(
+    _: AdminAccess,
+    Extension(base_url): Extension<BaseUrl>,
+    Extension(cache): Extension<Cache>,
+    Extension(database): Extension<DatabaseUser>,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(oauth): Extension<Oauth>,
+    Extension(random): Extension<Random>,
+    Json(request): Json<MintRequest>,
+) -> Result<Json<MintResponse>, ApiError> {
+    if request.token_type != MintedTokenType::Refresh {
+        return encode(&jwt, &oauth, &database, base_url.as_ref(), request)
+            .map(Json)
+            .map_err(ApiError::bad_request);
+    }
+    let (user_id, client_id) =
+        validate(&oauth, &database, &request).map_err(ApiError::bad_request)?;
+    let token = random.uuid().to_string();
+    cache
+        .insert_refresh_token(
+            token.clone(),
+            Grant {
+                act: None,
+                authorization_details: None,
+                client_id: client_id.unwrap_or_default(),
+                resource: request.audience,
+                scope: request.scope,
+                user_id,
+                auth_time: None,
+            },
+        )
+        .await;
+    Ok(Json(MintResponse {
+        token,
+        token_type: MintedTokenType::Refresh,
+        expires_at: None,
+    }))
+}
@@ -0,0 +1,116 @@
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use uuid::Uuid;
+
+use crate::entity::authorization::AuthorizationError;
+use crate::entity::user::User;
+use crate::service::database::DatabaseUser;
+use crate::service::random::Random;
+
+use super::super::ApiError;
+use super::AdminAccess;
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct UserPayload {
+    /// Generated when creating a user without an id.
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub email: String,
+}
+
+fn user_not_found() -> ApiError {
+    ApiError::not_found(AuthorizationError {
+        error: "user_not_found".into(),
+        error_description: "Unable to find the requested user.".into(),
+        state: None,
+    })
+}
+
+/// Checks the user before storing it, the email being used as a login hint.
+fn validate(database: &DatabaseUser, user: &User) -> Result<(), ApiError> {
+    if user.name.trim().is_empty() || !user.email.contains('@') {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: "The user requires a name and a valid email.".into(),
+            state: None,
+        }));
+    }
+    if database
+        .find(&user.email)
+        .is_some_and(|other| other.id != user.id)
+    {
+        return Err(ApiError::conflict(AuthorizationError {
+            error: "user_exists".into(),
+            error_description: "Another user already has this email.".into(),
+            state: None,
+        }));
+    }
+    Ok(())
+}
+
+pub(crate) async fn list(
+    _: AdminAccess,
+    Extension(database): Extension<DatabaseUser>,
+) -> Json<Vec<User>> {
+    Json(database.list())
+}
+
+pub(crate) async fn get(
+    _: AdminAccess,
+    Extension(database): Extension<DatabaseUser>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<User>, ApiError> {
+    database.get(&user_id).map(Json).ok_or_else(user_not_found)
+}
+
+pub(crate) async fn create(
+    _: AdminAccess,
+    Extension(database): Extension<DatabaseUser>,
+    Extension(random): Extension<Random>,
+    Json(payload): Json<UserPayload>,
+) -> Result<(StatusCode, Json<User>), ApiError> {
+    let user = User {
+        id: payload.id.unwrap_or_else(|| random.uuid()),
+        name: payload.name,
+        email: payload.email,
+    };
+    if database.contains(&user.id) {
+        return Err(ApiError::conflict(AuthorizationError {
+            error: "user_exists".into(),
+            error_description: "A user already has this id.".into(),
+            state: None,
+        }));
+    }
+    validate(&database, &user)?;
+    database.insert(user.clone());
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+pub(crate) async fn update(
+    _: AdminAccess,
+    Extension(database): Extension<DatabaseUser>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<UserPayload>,
+) -> Result<Json<User>, ApiError> {
+    if !database.contains(&user_id) {
+        return Err(user_not_found());
+    }
+    let user = User {
+        id: user_id,
+        name: payload.name,
+        email: payload.email,
+    };
+    validate(&database, &user)?;
+    database.insert(user.clone());
+    Ok(Json(user))
+}
+
+pub(crate) async fn delete(
+    _: AdminAccess,
+    Extension(database): Extension<DatabaseUser>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    database
+        .remove(&user_id)
+        .map(|_| StatusCode::NO_CONTENT)
+        .ok_or_else(user_not_found)
+}
@@ -1,34 +1,160 @@
 use axum::{
     extract::Query,
-    response::{Html, Redirect},
+    response::{Html, IntoResponse, Response},
     Extension,
 };
 use std::fmt::Write;
 
 use crate::{
-    entity::authorization::AuthorizationRequest,
-    service::{cache::Cache, database::DatabaseUser, oauth::Oauth},
+    entity::authorization::{AuthorizationError, AuthorizationReference, AuthorizationRequest},
+    service::{cache::Cache, database::DatabaseUser, oauth::Client},
 };
 
+use super::{escape_html, http_client, parse_params, ApiError, Responder};
+
+/// Maximum size of a fetched request object.
+const REQUEST_OBJECT_MAX_SIZE: usize = 64 * 1024;
+
+async fn read_request_object(request_uri: &str) -> Result<String, String> {
+    let mut res = http_client()
+        .get(request_uri)
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|err| err.to_string())?;
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await.map_err(|err| err.to_string())? {
+        if body.len() + chunk.len() > REQUEST_OBJECT_MAX_SIZE {
+            return Err(format!(
+                "the request object is larger than {REQUEST_OBJECT_MAX_SIZE} bytes"
+            ));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    String::from_utf8(body).map_err(|err| err.to_string())
+}
+
+/// Fetches the request object, only from the URLs registered by the client.
+async fn fetch_request_object(client: &Client, request_uri: &str) -> Result<String, ApiError> {
+    if !client.allows_request_uri(request_uri) {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_request_uri".into(),
+            error_description: "The request_uri isn't registered for the client.".into(),
+            state: None,
+        }));
+    }
+    read_request_object(request_uri).await.map_err(|err| {
+        tracing::error!("unable to fetch request object from {request_uri:?}: {err}");
+        ApiError::bad_request(AuthorizationError {
+            error: "invalid_request_uri".into(),
+            error_description: "Unable to fetch the request object from the request_uri.".into(),
+            state: None,
+        })
+    })
+}
+
+/// Loads the authorization request, either from the query parameters, from a request object
+/// or from the pushed authorization requests when a `request_uri` is provided.
+async fn resolve_request(
+    cache: &Cache,
+    responder: &Responder,
+    params: &[(String, String)],
+) -> Result<(AuthorizationRequest, bool), ApiError> {
+    let reference = parse_params::<AuthorizationReference>(params)
+        .ok()
+        .filter(AuthorizationReference::is_reference);
+    let Some(reference) = reference else {
+        return parse_params::<AuthorizationRequest>(params)
+            .map(|request| (request, false))
+            .map_err(|err| {
+                ApiError::bad_request(AuthorizationError {
+                    error: "invalid_request".into(),
+                    error_description: format!("Unable to parse the authorization request: {err}")
+                        .into(),
+                    state: None,
+                })
+            });
+    };
+
+    let request_object = match (reference.request, reference.request_uri) {
+        (Some(_), Some(_)) => {
+            return Err(ApiError::bad_request(AuthorizationError {
+                error: "invalid_request".into(),
+                error_description: "The request and request_uri parameters are exclusive.".into(),
+                state: None,
+            }));
+        }
+        (None, Some(request_uri))
+            if request_uri.starts_with(AuthorizationReference::PUSHED_REQUEST_URI_PREFIX) =>
+        {
+            let request = cache
+                .remove_pushed_authorization_request(&request_uri)
+                .await
+                .filter(|request| request.client_id == reference.client_id)
+                .ok_or_else(|| {
+                    ApiError::bad_request(AuthorizationError {
+                        error: "invalid_request_uri".into(),
+                        error_description:
+                            "Unable to find a pushed authorization request with the provided request_uri."
+                                .into(),
+                        state: None,
+                    })
+                })?;
+            return Ok((request, true));
+        }
+        (None, Some(request_uri)) => {
+            let client = responder
+                .oauth
+                .client(&reference.client_id)
+                .map_err(ApiError::bad_request)?;
+            fetch_request_object(&client, &request_uri).await?
+        }
+        (Some(request), None) => request,
+        (None, None) => unreachable!("references have a request or a request_uri"),
+    };
+
+    responder
+        .oauth
+        .decode_request_object(
+            &responder.jwt,
+            responder.base_url.as_ref(),
+            &reference.client_id,
+            request_object.trim(),
+        )
+        .map(|request| (request, false))
+        .map_err(ApiError::bad_request)
+}
+
 pub(crate) async fn handler(
     Extension(database): Extension<DatabaseUser>,
     Extension(cache): Extension<Cache>,
-    Extension(oauth): Extension<Oauth>,
-    Query(params): Query<AuthorizationRequest>,
-) -> Result<Html<String>, Redirect> {
-    if let Err(error) = oauth.check(&params) {
-        return Err(Redirect::temporary(
-            &error.as_redirect_url(&params.redirect_uri),
-        ));
+    responder: Responder,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Result<Html<String>, Response> {
+    let (params, pushed) = resolve_request(&cache, &responder, &query)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    // the errors can't be sent to a redirect_uri the client didn't register
+    let client = responder
+        .oauth
+        .redirect_client(&params)
+        .map_err(|error| ApiError::bad_request(error).into_response())?;
+    if let Err(error) = client
+        .check(&params)
+        .and_then(|_| client.check_pushed(&params, pushed))
+    {
+        return Err(responder.send(&params, &error));
     }
     let links = database
-        .as_ref()
-        .values()
+        .list()
+        .iter()
         .fold(String::default(), |mut res, user| {
             write!(
                 &mut res,
                 "<p><a href=\"/api/redirect/{}/{}\">Login as {}</a></p>",
-                params.state, user.id, user.name
+                escape_html(&params.state),
+                user.id,
+                escape_html(&user.name)
             )
             .unwrap();
             res
@@ -0,0 +1,281 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query},
+    http::header::{AUTHORIZATION, CONTENT_TYPE, SET_COOKIE},
+    response::{AppendHeaders, Html, IntoResponse, Redirect, Response},
+    Extension, Form, Json,
+};
+
+use crate::entity::accesstoken::AccessTokenResponse;
+use crate::entity::authorization::AuthorizationError;
+use crate::entity::backchannel::{
+    BackchannelAuthentication, BackchannelAuthenticationRequest, BackchannelAuthenticationResponse,
+    BackchannelNotification, BackchannelStatus, BackchannelTokenDeliveryMode,
+};
+use crate::service::admin::Admin;
+use crate::service::cache::Cache;
+use crate::service::clock::Clock;
+use crate::service::database::DatabaseUser;
+use crate::service::jsonwebtoken::{AccessTokenParams, JsonWebToken};
+use crate::service::oauth::Oauth;
+use crate::service::random::Random;
+
+use super::admin::{AdminAccess, ADMIN_TOKEN_COOKIE, ADMIN_TOKEN_PARAM};
+use super::{escape_html, http_client, parse_params, ApiError, ClientCredentials, MaliciousMode};
+
+/// Minimum delay between two polls of the token endpoint, in seconds.
+pub(crate) const POLLING_INTERVAL: u64 = 5;
+
+fn invalid_request(description: impl Into<std::borrow::Cow<'static, str>>) -> ApiError {
+    ApiError::bad_request(AuthorizationError {
+        error: "invalid_request".into(),
+        error_description: description.into(),
+        state: None,
+    })
+}
+
+/// Starts the authentication of a user on a separate device, as defined by CIBA.
+pub(crate) async fn handler(
+    Extension(oauth): Extension<Oauth>,
+    Extension(cache): Extension<Cache>,
+    Extension(clock): Extension<Clock>,
+    Extension(database): Extension<DatabaseUser>,
+    Extension(random): Extension<Random>,
+    credentials: ClientCredentials,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Result<Json<BackchannelAuthenticationResponse>, ApiError> {
+    let payload = parse_params::<BackchannelAuthenticationRequest>(&form).map_err(|err| {
+        invalid_request(format!(
+            "Unable to parse the backchannel authentication request: {err}"
+        ))
+    })?;
+    let client = credentials.authenticate(&oauth, payload.client_id.as_deref())?;
+    let delivery_mode = client
+        .backchannel_token_delivery_mode()
+        .map_err(ApiError::bad_request)?;
+    if delivery_mode.notifies() && payload.client_notification_token.is_none() {
+        return Err(invalid_request(
+            "The client_notification_token is required with the ping and push modes.",
+        ));
+    }
+
+    let login_hint = payload
+        .login_hint
+        .as_deref()
+        .ok_or_else(|| invalid_request("The login_hint parameter is required."))?;
+    let user = database.find(login_hint).ok_or_else(|| {
+        ApiError::bad_request(AuthorizationError {
+            error: "unknown_user_id".into(),
+            error_description: "Unable to find a user matching the login_hint.".into(),
+            state: None,
+        })
+    })?;
+
+    let expires_in = match payload.requested_expiry.as_deref() {
+        Some(value) => value
+            .parse::<u64>()
+            .map_err(|_| invalid_request("The requested_expiry must be a number of seconds."))?
+            .min(cache.backchannel_authentication_ttl().as_secs()),
+        None => cache.backchannel_authentication_ttl().as_secs(),
+    };
+    let auth_req_id = random.uuid().to_string();
+    cache
+        .insert_backchannel_authentication(BackchannelAuthentication {
+            auth_req_id: auth_req_id.clone(),
+            binding_message: payload.binding_message,
+            client_id: client.client_id().to_string(),
+            client_notification_token: payload.client_notification_token,
+            delivery_mode,
+            expires_at: clock.now() + Duration::from_secs(expires_in),
+            last_polled_at: None,
+            scope: payload.scope,
+            status: BackchannelStatus::Pending,
+            user_id: user.id,
+            auth_time: None,
+        })
+        .await;
+
+    Ok(Json(BackchannelAuthenticationResponse {
+        auth_req_id,
+        expires_in,
+        interval: (delivery_mode != BackchannelTokenDeliveryMode::Push).then_some(POLLING_INTERVAL),
+    }))
+}
+
+fn pending_page(
+    oauth: &Oauth,
+    database: &DatabaseUser,
+    pending: &[BackchannelAuthentication],
+) -> String {
+    let items = pending.iter().fold(String::default(), |mut res, req| {
+        let user = database
+            .get(&req.user_id)
+            .map(|user| user.name)
+            .unwrap_or_default();
+        let client = oauth.client(&req.client_id);
+        let client_name = client
+            .as_ref()
+            .map(|client| client.client_name())
+            .unwrap_or(&req.client_id);
+        write!(
+            &mut res,
+            "<form method=\"post\" action=\"/admin/backchannel/{}\">\
+            <p>{} is requesting access to the account of {}.</p>\
+            <p>Binding message: {}</p><p>Scope: {}</p>\
+            <button type=\"submit\" name=\"decision\" value=\"approve\">Approve</button>\
+            <button type=\"submit\" name=\"decision\" value=\"deny\">Deny</button>\
+            </form>",
+            escape_html(&req.auth_req_id),
+            escape_html(client_name),
+            escape_html(&user),
+            escape_html(req.binding_message.as_deref().unwrap_or_default()),
+            escape_html(req.scope.as_deref().unwrap_or_default()),
+        )
+        .unwrap();
+        res
+    });
+    format!(
+        "<!DOCTYPE html><html><head><title>Backchannel authentications</title></head><body>\
+        <h1>Pending backchannel authentications</h1>{items}</body></html>"
+    )
+}
+
+/// Lists the backchannel authentications waiting for a tester to approve or deny them.
+///
+/// The admin token given in the query is kept in a cookie, so that the decisions are authorized,
+/// as long as it is the valid one.
+pub(crate) async fn pending(
+    _: AdminAccess,
+    Extension(admin): Extension<Admin>,
+    Extension(oauth): Extension<Oauth>,
+    Extension(cache): Extension<Cache>,
+    Extension(database): Extension<DatabaseUser>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Response {
+    let pending = cache.pending_backchannel_authentications();
+    let page = Html(pending_page(&oauth, &database, &pending));
+    let token = params
+        .into_iter()
+        .find(|(key, _)| key == ADMIN_TOKEN_PARAM)
+        .map(|(_, value)| value)
+        .filter(|token| admin.authorize(token));
+    match token {
+        Some(token) => (
+            AppendHeaders([(
+                SET_COOKIE,
+                format!("{ADMIN_TOKEN_COOKIE}={token}; Path=/admin; HttpOnly; SameSite=Strict"),
+            )]),
+            page,
+        )
+            .into_response(),
+        None => page.into_response(),
+    }
+}
+
+/// Calls the client notification endpoint, in ping and push modes.
+async fn notify(endpoint: &str, token: &str, notification: &BackchannelNotification) {
+    let body = serde_json::to_string(notification).unwrap_or_default();
+    let res = http_client()
+        .post(endpoint)
+        .header(AUTHORIZATION, format!("Bearer {token}"))
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await;
+    if let Err(err) = res.and_then(|res| res.error_for_status()) {
+        tracing::warn!("unable to notify the client at {endpoint}: {err:?}");
+    }
+}
+
+/// Records the decision of the tester on a pending backchannel authentication.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn decision(
+    _: AdminAccess,
+    Extension(oauth): Extension<Oauth>,
+    Extension(cache): Extension<Cache>,
+    Extension(clock): Extension<Clock>,
+    Extension(jwt): Extension<JsonWebToken>,
+    malicious: MaliciousMode,
+    Path(auth_req_id): Path<String>,
+    Form(params): Form<Vec<(String, String)>>,
+) -> Result<Redirect, ApiError> {
+    let approved = params
+        .iter()
+        .any(|(key, value)| key == "decision" && value == "approve");
+    let now = clock.now();
+    let auth_time = clock.timestamp();
+    let req = cache
+        .update_backchannel_authentication(&auth_req_id, |req| {
+            if req.status != BackchannelStatus::Pending || req.is_expired(now) {
+                return false;
+            }
+            req.auth_time = Some(auth_time);
+            req.status = if approved {
+                BackchannelStatus::Approved
+            } else {
+                BackchannelStatus::Denied
+            };
+            true
+        })
+        .await
+        .ok_or_else(|| {
+            ApiError::bad_request(AuthorizationError {
+                error: "auth_req_id_unknown".into(),
+                error_description:
+                    "Unable to find a pending backchannel authentication with this auth_req_id."
+                        .into(),
+                state: None,
+            })
+        })?;
+
+    let mut notification = BackchannelNotification {
+        auth_req_id: auth_req_id.clone(),
+        token: None,
+        error: None,
+        error_description: None,
+    };
+    if req.delivery_mode == BackchannelTokenDeliveryMode::Push {
+        // the tokens are delivered right away, nothing is left to poll for
+        cache.remove_backchannel_authentication(&auth_req_id).await;
+        if approved {
+            let (access_token, _) = jwt.encode(AccessTokenParams {
+                user_id: req.user_id,
+                cnf: None,
+                audience: Vec::new(),
+                act: None,
+                authorization_details: None,
+                auth_time: req.auth_time,
+                lifetime: None,
+                claims: Default::default(),
+                tampering: malicious.tampering(&req.client_id, req.user_id),
+            });
+            notification.token = Some(AccessTokenResponse {
+                access_token,
+                issued_token_type: None,
+                token_type: "Bearer",
+                expires_in: Some(jwt.lifetime().as_secs()),
+                refresh_token: None,
+                scope: req.scope.clone(),
+                authorization_details: None,
+            });
+        } else {
+            notification.error = Some("access_denied");
+            notification.error_description = Some("The user denied the authentication request.");
+        }
+    }
+
+    let client = oauth.client(&req.client_id).ok();
+    if let (true, Some(endpoint), Some(token)) = (
+        req.delivery_mode.notifies(),
+        client
+            .as_ref()
+            .and_then(|client| client.backchannel_client_notification_endpoint()),
+        req.client_notification_token.as_deref(),
+    ) {
+        notify(endpoint, token, &notification).await;
+    }
+
+    Ok(Redirect::to("/admin/backchannel"))
+}
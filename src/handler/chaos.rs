@@ -0,0 +1,123 @@
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{MatchedPath, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures_util::future::BoxFuture;
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::chaos::{Chaos, ChaosScenario};
+
+use super::{peek_body, ApiError, INSPECTED_BODY_MAX_SIZE};
+
+/// Body failing after the given bytes, which makes the server drop the connection.
+fn broken_body(sent: Bytes) -> Body {
+    let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+        Ok(sent),
+        Err(std::io::Error::new(
+            std::io::ErrorKind::ConnectionReset,
+            "connection dropped on purpose",
+        )),
+    ];
+    Body::from_stream(futures_util::stream::iter(chunks))
+}
+
+async fn degrade(chaos: &Chaos, scenario: ChaosScenario, response: Response) -> Response {
+    if chaos.roll(scenario.reset_rate) {
+        let (parts, _) = response.into_parts();
+        return Response::from_parts(parts, broken_body(Bytes::new()));
+    }
+    if chaos.roll(scenario.truncate_rate) {
+        // the content length still announces the whole body, the larger ones being left alone
+        let (mut parts, body) = response.into_parts();
+        let bytes = match peek_body(body, INSPECTED_BODY_MAX_SIZE).await {
+            (_, Some(bytes)) => bytes,
+            (body, None) => return Response::from_parts(parts, body),
+        };
+        parts
+            .headers
+            .insert(axum::http::header::CONTENT_LENGTH, bytes.len().into());
+        return Response::from_parts(parts, broken_body(bytes.slice(..bytes.len() / 2)));
+    }
+    response
+}
+
+/// Applies the chaos scenarios to the requests of the router.
+#[derive(Clone)]
+pub(crate) struct ChaosLayer(pub Chaos);
+
+impl<S> tower::Layer<S> for ChaosLayer {
+    type Service = ChaosService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ChaosService {
+            chaos: self.0.clone(),
+            inner,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ChaosService<S> {
+    chaos: Chaos,
+    inner: S,
+}
+
+impl<S> tower::Service<Request> for ChaosService<S>
+where
+    S: tower::Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|route| route.as_str().to_string());
+        let scenario = self.chaos.scenario(request.uri().path(), route.as_deref());
+        // the inner service that was polled ready is the one to call
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let chaos = self.chaos.clone();
+        Box::pin(async move {
+            let Some(scenario) = scenario else {
+                return inner.call(request).await;
+            };
+            tracing::debug!("applying chaos scenario {}", scenario.name);
+            tokio::time::sleep(chaos.delay(&scenario)).await;
+            if chaos.roll(scenario.hang_rate) {
+                std::future::pending::<()>().await;
+            }
+            if chaos.roll(scenario.failure_rate) {
+                let status = scenario
+                    .failure_status
+                    .and_then(|status| StatusCode::from_u16(status).ok())
+                    .unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+                return Ok(ApiError::new(
+                    status,
+                    AuthorizationError {
+                        error: "temporarily_unavailable".into(),
+                        error_description: "The request failed on purpose.".into(),
+                        state: None,
+                    },
+                )
+                .into_response());
+            }
+            let response = inner.call(request).await?;
+            Ok(degrade(&chaos, scenario, response).await)
+        })
+    }
+}
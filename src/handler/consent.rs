@@ -0,0 +1,60 @@
+use axum::{extract::Path, response::Response, Extension, Form};
+use uuid::Uuid;
+
+use crate::{
+    entity::authorization::AuthorizationError,
+    service::{
+        cache::Cache, clock::Clock, consent::ConsentStore, database::DatabaseUser, random::Random,
+    },
+};
+
+use super::redirect::{find_request, Issuer};
+use super::{ApiError, MaliciousMode, Responder};
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn handler(
+    Extension(database): Extension<DatabaseUser>,
+    Extension(cache): Extension<Cache>,
+    Extension(clock): Extension<Clock>,
+    Extension(consent): Extension<ConsentStore>,
+    Extension(random): Extension<Random>,
+    malicious: MaliciousMode,
+    responder: Responder,
+    Path((state, user_id)): Path<(String, Uuid)>,
+    Form(params): Form<Vec<(String, String)>>,
+) -> Result<Response, ApiError> {
+    let request = find_request(&database, &cache, state, user_id).await?;
+
+    let approved = params
+        .iter()
+        .any(|(key, value)| key == "decision" && value == "approve");
+    if !approved {
+        let error = AuthorizationError {
+            error: "access_denied".into(),
+            error_description: "The user denied the authorization request.".into(),
+            state: Some(request.state.clone()),
+        };
+        return Ok(responder.send(&request, &error));
+    }
+
+    // only the scopes that were both requested and approved are granted
+    let granted = request
+        .scopes()
+        .filter(|scope| {
+            params
+                .iter()
+                .any(|(key, value)| key == "scope" && value == scope)
+        })
+        .collect::<Vec<_>>();
+    consent.grant(user_id, &request.client_id, granted.iter().copied());
+
+    let scope = Some(granted.join(" ")).filter(|scope| !scope.is_empty());
+    let issuer = Issuer {
+        cache: &cache,
+        clock: &clock,
+        malicious,
+        random: &random,
+        responder,
+    };
+    Ok(issuer.respond(request, user_id, scope).await)
+}
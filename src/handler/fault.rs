@@ -0,0 +1,175 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use uuid::Uuid;
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::{
+    cache::Cache, database::DatabaseUser, fault::Faults, jsonwebtoken::JsonWebToken,
+    opaquetoken::OpaqueTokens,
+};
+
+use super::{peek_body, request_client_id, ApiError, Responder, INSPECTED_BODY_MAX_SIZE};
+
+/// Endpoints where the user decides, with the state and the user in their path.
+const DECISION_PATHS: &[&str] = &["/api/consent/", "/api/redirect/"];
+
+/// Parameters identifying the user of the request.
+#[derive(Default, serde::Deserialize)]
+struct UserReference {
+    auth_req_id: Option<String>,
+    code: Option<String>,
+    login_hint: Option<String>,
+    refresh_token: Option<String>,
+}
+
+/// State and user of the requests on the decision endpoints.
+fn decision(path: &str) -> Option<(&str, Option<Uuid>)> {
+    let rest = DECISION_PATHS
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))?;
+    let (state, user_id) = rest.split_once('/')?;
+    Some((state, user_id.parse().ok()))
+}
+
+/// Services used to find the user of a request, without consuming its code or tokens.
+struct Lookup {
+    cache: Cache,
+    database: DatabaseUser,
+    jwt: JsonWebToken,
+    tokens: OpaqueTokens,
+}
+
+impl Lookup {
+    async fn user_id(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+        params: UserReference,
+    ) -> Option<Uuid> {
+        if let Some((_, user_id)) = decision(path) {
+            return user_id;
+        }
+        let bearer = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split_once(' '))
+            .filter(|(scheme, _)| !scheme.eq_ignore_ascii_case("basic"))
+            .map(|(_, token)| token.trim());
+        if let Some(token) = bearer {
+            return match self.tokens.find(token) {
+                Some(opaque) => Some(opaque.user_id),
+                None => self.jwt.decode(token).map(|claims| claims.sub),
+            };
+        }
+        if let Some(code) = params.code {
+            return self
+                .cache
+                .get_authorization_response(&code)
+                .await
+                .map(|response| response.user_id);
+        }
+        if let Some(refresh_token) = params.refresh_token {
+            return self
+                .cache
+                .get_refresh_token(&refresh_token)
+                .await
+                .map(|grant| grant.user_id);
+        }
+        if let Some(auth_req_id) = params.auth_req_id {
+            return self
+                .cache
+                .get_backchannel_authentication(&auth_req_id)
+                .await
+                .map(|req| req.user_id);
+        }
+        params
+            .login_hint
+            .and_then(|hint| self.database.find(&hint))
+            .map(|user| user.id)
+    }
+}
+
+/// Fails the requests matching a fault injection rule, before they reach their handler.
+///
+/// On the decision endpoints, the error is sent to the client with its response mode,
+/// unless the rule sets a status.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn inject(
+    Extension(faults): Extension<Faults>,
+    Extension(cache): Extension<Cache>,
+    Extension(database): Extension<DatabaseUser>,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(tokens): Extension<OpaqueTokens>,
+    responder: Responder,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| route.as_str().to_string());
+    if !faults.targets(&path, route.as_deref()) {
+        return next.run(request).await;
+    }
+
+    // the rules are matched without the parameters of a body too large to be read
+    let (parts, body) = request.into_parts();
+    let (body, content) = peek_body(body, INSPECTED_BODY_MAX_SIZE).await;
+    let content = content.unwrap_or_default();
+    let query = parts.uri.query();
+    let params = query
+        .and_then(|query| serde_qs::from_str::<UserReference>(query).ok())
+        .or_else(|| serde_qs::from_bytes::<UserReference>(&content).ok())
+        .unwrap_or_default();
+    let lookup = Lookup {
+        cache,
+        database,
+        jwt,
+        tokens,
+    };
+    let client_id = request_client_id(&parts.headers, query, &content);
+    let user_id = lookup.user_id(&path, &parts.headers, params).await;
+
+    let Some(rule) = faults.check(&path, route.as_deref(), client_id.as_deref(), user_id) else {
+        return next.run(Request::from_parts(parts, body)).await;
+    };
+    tracing::debug!("injecting fault {} on {path}", rule.id);
+    let status = rule
+        .status
+        .and_then(|status| StatusCode::from_u16(status).ok())
+        .unwrap_or(if rule.error.is_some() {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        });
+    if let Some(body) = rule.body {
+        return (status, body).into_response();
+    }
+    let error = AuthorizationError {
+        error: rule
+            .error
+            .unwrap_or_else(|| String::from("server_error"))
+            .into(),
+        error_description: rule
+            .error_description
+            .unwrap_or_else(|| String::from("The request failed on purpose."))
+            .into(),
+        state: None,
+    };
+    if let Some((state, _)) = decision(&path).filter(|_| rule.status.is_none()) {
+        if let Some(request) = lookup.cache.remove_authorization_request(state).await {
+            let error = AuthorizationError {
+                state: Some(request.state.clone()),
+                ..error
+            };
+            return responder.send(&request, &error);
+        }
+    }
+    ApiError::new(status, error).into_response()
+}
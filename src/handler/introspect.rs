@@ -0,0 +1,61 @@
+use std::time::SystemTime;
+
+use axum::{Extension, Form, Json};
+
+use crate::entity::introspection::{IntrospectionRequest, IntrospectionResponse};
+use crate::service::jsonwebtoken::JsonWebToken;
+use crate::service::oauth::Oauth;
+use crate::service::opaquetoken::OpaqueTokens;
+
+use super::{ApiError, ClientCredentials};
+
+/// Describes an access token to an authenticated client, as defined by RFC 7662.
+pub(crate) async fn handler(
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(oauth): Extension<Oauth>,
+    Extension(tokens): Extension<OpaqueTokens>,
+    credentials: ClientCredentials,
+    Form(payload): Form<IntrospectionRequest>,
+) -> Result<Json<IntrospectionResponse>, ApiError> {
+    credentials.authenticate(&oauth, payload.client_id.as_deref())?;
+
+    if let Some(opaque) = tokens.find(&payload.token) {
+        return Ok(Json(IntrospectionResponse {
+            active: true,
+            scope: opaque.scope,
+            token_type: Some("Bearer"),
+            exp: opaque.expires_at.map(|expires_at| {
+                expires_at
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            }),
+            sub: Some(opaque.user_id),
+            ..Default::default()
+        }));
+    }
+
+    // invalid and expired tokens are only reported as inactive
+    let Some(mut claims) = jwt.decode(&payload.token) else {
+        return Ok(Json(IntrospectionResponse::default()));
+    };
+    let bound = claims.cnf.as_ref().is_some_and(|cnf| cnf.jkt.is_some());
+    let mut claim = |name: &str| {
+        claims
+            .claims
+            .remove(name)
+            .and_then(|value| value.as_str().map(String::from))
+    };
+    let (scope, client_id) = (claim("scope"), claim("client_id"));
+    Ok(Json(IntrospectionResponse {
+        active: true,
+        scope,
+        client_id,
+        token_type: Some(if bound { "DPoP" } else { "Bearer" }),
+        exp: Some(claims.exp as u64),
+        sub: Some(claims.sub),
+        aud: claims.aud,
+        act: claims.act,
+        cnf: claims.cnf,
+    }))
+}
@@ -0,0 +1,89 @@
+use std::{
+    collections::BTreeMap,
+    time::{Instant, SystemTime},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::service::journal::{Journal, JournalEntry};
+
+use super::{peek_body, request_client_id, INSPECTED_BODY_MAX_SIZE};
+
+/// Requests of the journal API itself, that are not recorded.
+const JOURNAL_PATH: &str = "/admin/journal";
+
+fn headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// Reads the body to record it, a marker being recorded for the large or broken ones.
+async fn buffer(body: Body) -> (Body, Option<Bytes>, String) {
+    let (body, content) = peek_body(body, INSPECTED_BODY_MAX_SIZE).await;
+    let recorded = match &content {
+        Some(content) => String::from_utf8_lossy(content).into_owned(),
+        None => format!(
+            "<body larger than {INSPECTED_BODY_MAX_SIZE} bytes or unreadable, not recorded>"
+        ),
+    };
+    (body, content, recorded)
+}
+
+/// Records the request and its response in the journal.
+pub(crate) async fn record(
+    State(journal): State<Journal>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.uri().path() == JOURNAL_PATH {
+        return next.run(request).await;
+    }
+    let started = Instant::now();
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    let (parts, body) = request.into_parts();
+    let (body, content, request_body) = buffer(body).await;
+    let mut entry = JournalEntry {
+        id: journal.next_id(),
+        timestamp,
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(String::from),
+        client_id: request_client_id(
+            &parts.headers,
+            parts.uri.query(),
+            content.as_deref().unwrap_or_default(),
+        ),
+        request_headers: headers(&parts.headers),
+        request_body,
+        status: 0,
+        response_headers: BTreeMap::new(),
+        response_body: String::new(),
+        duration_ms: 0,
+    };
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    let (parts, body) = response.into_parts();
+    let (body, _, response_body) = buffer(body).await;
+    entry.status = parts.status.as_u16();
+    entry.response_headers = headers(&parts.headers);
+    entry.response_body = response_body;
+    entry.duration_ms = started.elapsed().as_millis() as u64;
+    journal.record(entry);
+
+    Response::from_parts(parts, body)
+}
@@ -0,0 +1,7 @@
+use axum::{Extension, Json};
+
+use crate::service::jsonwebtoken::JsonWebToken;
+
+pub(crate) async fn handler(Extension(jwt): Extension<JsonWebToken>) -> Json<serde_json::Value> {
+    Json(jwt.jwks())
+}
@@ -1,36 +1,502 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    body::{Body, Bytes},
+    extract::{rejection::ExtensionRejection, FromRequestParts},
+    http::{request::Parts, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{AppendHeaders, Html, IntoResponse, Redirect, Response},
+    Extension,
+};
+use axum_extra::{
+    headers::{authorization::Basic, Authorization, HeaderMapExt},
+    TypedHeader,
+};
+use std::fmt::Write;
 
-use crate::entity::authorization::AuthorizationError;
+use crate::entity::authorization::{AuthorizationError, AuthorizationRequest, ResponseMode};
+use crate::service::{
+    baseurl::BaseUrl,
+    dpop::Dpop,
+    jsonwebtoken::{JsonWebToken, Tampering},
+    malicious::Malicious,
+    oauth::{Client, Oauth},
+    tls::ClientCertificate,
+};
 
+pub(crate) mod admin;
 pub(crate) mod authorize;
+pub(crate) mod backchannel;
+pub(crate) mod chaos;
+pub(crate) mod consent;
+pub(crate) mod fault;
+pub(crate) mod introspect;
+pub(crate) mod journal;
+pub(crate) mod jwks;
+pub(crate) mod par;
 pub(crate) mod redirect;
+pub(crate) mod registration;
 pub(crate) mod status;
 pub(crate) mod token;
 pub(crate) mod userinfo;
 
 pub(crate) struct ApiError {
     code: StatusCode,
+    headers: Vec<(&'static str, HeaderValue)>,
     inner: AuthorizationError,
 }
 
 impl ApiError {
-    pub fn bad_request(inner: AuthorizationError) -> Self {
+    pub fn new(code: StatusCode, inner: AuthorizationError) -> Self {
         Self {
-            code: StatusCode::BAD_REQUEST,
+            code,
+            headers: Vec::new(),
             inner,
         }
     }
 
+    pub fn bad_request(inner: AuthorizationError) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, inner)
+    }
+
     pub fn unauthorized(inner: AuthorizationError) -> Self {
-        Self {
-            code: StatusCode::UNAUTHORIZED,
-            inner,
+        Self::new(StatusCode::UNAUTHORIZED, inner)
+    }
+
+    pub fn not_found(inner: AuthorizationError) -> Self {
+        Self::new(StatusCode::NOT_FOUND, inner)
+    }
+
+    pub fn conflict(inner: AuthorizationError) -> Self {
+        Self::new(StatusCode::CONFLICT, inner)
+    }
+
+    pub fn internal(inner: AuthorizationError) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, inner)
+    }
+
+    pub fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            self.headers.push((name, value));
         }
+        self
     }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        (self.code, axum::Json(self.inner)).into_response()
+        (
+            self.code,
+            AppendHeaders(self.headers),
+            axum::Json(self.inner),
+        )
+            .into_response()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ClientReference {
+    client_id: Option<String>,
+}
+
+/// Client sending the request, from its credentials or its `client_id` parameter.
+pub(crate) fn request_client_id(
+    headers: &HeaderMap,
+    query: Option<&str>,
+    body: &[u8],
+) -> Option<String> {
+    let parameter = |params: &[u8]| {
+        serde_qs::from_bytes::<ClientReference>(params)
+            .ok()
+            .and_then(|reference| reference.client_id)
+    };
+    headers
+        .typed_get::<Authorization<Basic>>()
+        .map(|Authorization(basic)| basic.username().to_string())
+        .or_else(|| query.and_then(|query| parameter(query.as_bytes())))
+        .or_else(|| parameter(body))
+}
+
+/// Deserializes urlencoded parameters, collecting the repeated ones, like `resource`, in an array.
+pub(crate) fn parse_params<T: serde::de::DeserializeOwned>(
+    params: &[(String, String)],
+) -> Result<T, serde_json::Error> {
+    use serde_json::{map::Entry, Value};
+
+    let mut res = serde_json::Map::new();
+    for (key, value) in params {
+        match res.entry(key.as_str()) {
+            Entry::Vacant(entry) => {
+                entry.insert(Value::from(value.as_str()));
+            }
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Value::Array(items) => items.push(Value::from(value.as_str())),
+                other => *other = Value::Array(vec![other.take(), Value::from(value.as_str())]),
+            },
+        }
+    }
+    serde_json::from_value(Value::Object(res))
+}
+
+/// Credentials presented by the client: HTTP Basic and the TLS client certificate, if any.
+pub(crate) struct ClientCredentials {
+    pub basic: Option<Basic>,
+    pub certificate: Option<ClientCertificate>,
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientCredentials
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let basic = TypedHeader::<Authorization<Basic>>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|TypedHeader(Authorization(basic))| basic);
+        let certificate = parts.extensions.get::<ClientCertificate>().cloned();
+        Ok(Self { basic, certificate })
+    }
+}
+
+impl ClientCredentials {
+    /// Authenticates the client, returning its registration.
+    pub fn authenticate(&self, oauth: &Oauth, client_id: Option<&str>) -> Result<Client, ApiError> {
+        let basic = self
+            .basic
+            .as_ref()
+            .map(|basic| (basic.username(), basic.password()));
+        oauth
+            .authenticate_client(client_id, basic, self.certificate.as_ref())
+            .map_err(ApiError::bad_request)
+    }
+}
+
+/// Header requesting a tampered token for a single request.
+const MALICIOUS_TOKEN_HEADER: &str = "x-malicious-token";
+
+/// Flaw to put in the issued tokens, requested with a header or set for the client or the user.
+pub(crate) struct MaliciousMode {
+    requested: Option<Tampering>,
+    malicious: Malicious,
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for MaliciousMode
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let malicious = Extension::<Malicious>::from_request_parts(parts, state)
+            .await
+            .map(|Extension(malicious)| malicious)
+            .unwrap_or_default();
+        let requested = match parts.headers.get(MALICIOUS_TOKEN_HEADER) {
+            Some(value) => {
+                let tampering = value.to_str().ok().and_then(|value| value.parse().ok());
+                Some(tampering.ok_or_else(|| {
+                    ApiError::bad_request(AuthorizationError {
+                        error: "invalid_request".into(),
+                        error_description: "Unknown tampering in the x-malicious-token header."
+                            .into(),
+                        state: None,
+                    })
+                })?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            requested,
+            malicious,
+        })
+    }
+}
+
+impl MaliciousMode {
+    pub fn tampering(&self, client_id: &str, user_id: uuid::Uuid) -> Option<Tampering> {
+        self.requested
+            .or_else(|| self.malicious.tampering(client_id, user_id))
+    }
+}
+
+/// DPoP proof sent by the client in the `DPoP` header, if any.
+pub(crate) struct DpopProof {
+    base_url: BaseUrl,
+    dpop: Dpop,
+    proof: Option<String>,
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for DpopProof
+where
+    S: Send + Sync,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(base_url) = Extension::<BaseUrl>::from_request_parts(parts, state).await?;
+        let Extension(dpop) = Extension::<Dpop>::from_request_parts(parts, state).await?;
+        let proof = parts
+            .headers
+            .get("dpop")
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        Ok(Self {
+            base_url,
+            dpop,
+            proof,
+        })
+    }
+}
+
+impl DpopProof {
+    pub fn is_present(&self) -> bool {
+        self.proof.is_some()
+    }
+
+    /// Verifies the proof, if any, and returns the thumbprint of its key.
+    ///
+    /// Errors are built with the status matching the endpoint: `400` for the token
+    /// endpoint and `401` for the protected resources, that also get a `WWW-Authenticate` header.
+    pub async fn verify(
+        &self,
+        code: StatusCode,
+        method: &str,
+        path: &str,
+        access_token: Option<&str>,
+    ) -> Result<Option<String>, ApiError> {
+        let Some(proof) = self.proof.as_deref() else {
+            return Ok(None);
+        };
+        let url = format!("{}{path}", self.base_url.as_ref());
+        match self.dpop.verify(proof, method, &url, access_token).await {
+            Ok(jkt) => Ok(Some(jkt)),
+            Err(inner) => {
+                let authenticate = format!("DPoP error=\"{}\"", inner.error);
+                let use_nonce = inner.error == "use_dpop_nonce";
+                let mut error = ApiError::new(code, inner);
+                if use_nonce {
+                    error = error.with_header("dpop-nonce", &self.dpop.issue_nonce().await);
+                }
+                if code == StatusCode::UNAUTHORIZED {
+                    error = error.with_header("www-authenticate", &authenticate);
+                }
+                Err(error)
+            }
+        }
+    }
+}
+
+/// Timeout of the outgoing requests, so that a client that never answers can't hang a handler.
+const OUTGOING_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Client of the outgoing requests, shared to reuse the connections.
+pub(crate) fn http_client() -> &'static reqwest::Client {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(OUTGOING_REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("couldn't build the http client")
+    })
+}
+
+/// Size of the bodies read by the middlewares, the larger ones being passed on unread.
+pub(crate) const INSPECTED_BODY_MAX_SIZE: usize = 64 * 1024;
+
+/// Reads the body up to the limit, giving it back whole along with its content,
+/// that is missing when the body is larger or fails to be read.
+pub(crate) async fn peek_body(body: Body, limit: usize) -> (Body, Option<Bytes>) {
+    use futures_util::{stream, StreamExt};
+
+    let mut chunks = body.into_data_stream();
+    let mut content = Vec::new();
+    loop {
+        let rest = match chunks.next().await {
+            None => {
+                let content = Bytes::from(content);
+                return (Body::from(content.clone()), Some(content));
+            }
+            Some(Ok(chunk)) if content.len() + chunk.len() <= limit => {
+                content.extend_from_slice(&chunk);
+                continue;
+            }
+            Some(Ok(chunk)) => vec![Ok(Bytes::from(content)), Ok(chunk)],
+            // the error is left for the handler to deal with
+            Some(Err(err)) => vec![Ok(Bytes::from(content)), Err(err)],
+        };
+        return (Body::from_stream(stream::iter(rest).chain(chunks)), None);
+    }
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Origin of the given url, used as target of the `postMessage` call.
+fn origin(url: &str) -> &str {
+    let Some(index) = url.find("://") else {
+        return "*";
+    };
+    url[index + 3..]
+        .find('/')
+        .map(|end| &url[..index + 3 + end])
+        .unwrap_or(url)
+}
+
+fn form_post_page(url: &str, payload: &serde_json::Map<String, serde_json::Value>) -> String {
+    let inputs = payload
+        .iter()
+        .fold(String::default(), |mut res, (name, value)| {
+            let value = match value {
+                serde_json::Value::Null => return res,
+                serde_json::Value::String(inner) => inner.clone(),
+                other => other.to_string(),
+            };
+            write!(
+                &mut res,
+                "<input type=\"hidden\" name=\"{}\" value=\"{}\" />",
+                escape_html(name),
+                escape_html(&value)
+            )
+            .unwrap();
+            res
+        });
+    format!(
+        "<!DOCTYPE html><html><head><title>Submit This Form</title></head>\
+        <body onload=\"javascript:document.forms[0].submit()\">\
+        <form method=\"post\" action=\"{}\">{inputs}\
+        <noscript><button type=\"submit\">Continue</button></noscript>\
+        </form></body></html>",
+        escape_html(url)
+    )
+}
+
+fn web_message_page(url: &str, payload: &serde_json::Map<String, serde_json::Value>) -> String {
+    let message = serde_json::json!({
+        "type": "authorization_response",
+        "response": payload,
+    });
+    // avoid closing the script tag from within the payload
+    let message = message.to_string().replace("</", "<\\/");
+    let origin = serde_json::Value::from(origin(url)).to_string();
+    format!(
+        "<!DOCTYPE html><html><head><title>Authorization Response</title></head><body>\
+        <script type=\"text/javascript\">\
+        (function(target) {{ target.postMessage({message}, {origin}); }})(window.opener || window.parent);\
+        </script></body></html>"
+    )
+}
+
+fn authorization_response(
+    url: &str,
+    mode: ResponseMode,
+    payload: serde_json::Map<String, serde_json::Value>,
+    after_post: bool,
+) -> Response {
+    // a 307 would have the browser post the form again to the client
+    let redirect = |uri: String| {
+        if after_post {
+            Redirect::to(&uri).into_response()
+        } else {
+            Redirect::temporary(&uri).into_response()
+        }
+    };
+    match mode {
+        ResponseMode::Query => {
+            let query = serde_qs::to_string(&payload).unwrap();
+            redirect(format!("{url}?{query}"))
+        }
+        ResponseMode::Fragment => {
+            let query = serde_qs::to_string(&payload).unwrap();
+            redirect(format!("{url}#{query}"))
+        }
+        ResponseMode::FormPost => Html(form_post_page(url, &payload)).into_response(),
+        ResponseMode::WebMessage => Html(web_message_page(url, &payload)).into_response(),
+        other => unreachable!("{other:?} is not a transport response mode"),
+    }
+}
+
+/// Sends authorization responses (or errors) back to the client.
+pub(crate) struct Responder {
+    pub base_url: BaseUrl,
+    pub jwt: JsonWebToken,
+    pub oauth: Oauth,
+    /// Whether the request is a form submission, redirected with a 303 instead of a 307.
+    pub after_post: bool,
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Responder
+where
+    S: Send + Sync,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Extension(base_url) = Extension::<BaseUrl>::from_request_parts(parts, state).await?;
+        let Extension(jwt) = Extension::<JsonWebToken>::from_request_parts(parts, state).await?;
+        let Extension(oauth) = Extension::<Oauth>::from_request_parts(parts, state).await?;
+        Ok(Self {
+            base_url,
+            jwt,
+            oauth,
+            after_post: parts.method == Method::POST,
+        })
+    }
+}
+
+impl Responder {
+    /// Sends the payload using the response mode of the request, wrapped in a signed JWT if requested.
+    pub fn send<T: serde::Serialize>(
+        &self,
+        request: &AuthorizationRequest,
+        payload: &T,
+    ) -> Response {
+        let serde_json::Value::Object(mut payload) = serde_json::to_value(payload).unwrap() else {
+            unreachable!("authorization responses are serialized as objects");
+        };
+        payload.retain(|_, value| !value.is_null());
+
+        // the client may have been removed or updated since the request was checked
+        let client = match self.oauth.redirect_client(request) {
+            Ok(client) => client,
+            Err(error) => return ApiError::bad_request(error).into_response(),
+        };
+        let mode = request.response_mode();
+        if mode.is_jwt() {
+            let response = match self.jwt.encode_response(
+                client.authorization_signed_response_alg(),
+                client.client_secret(),
+                self.base_url.as_ref(),
+                &request.client_id,
+                payload,
+            ) {
+                Ok(value) => value,
+                Err(err) => {
+                    tracing::error!("unable to sign authorization response: {err:?}");
+                    return ApiError::internal(AuthorizationError {
+                        error: "server_error".into(),
+                        error_description: "Unable to sign the authorization response.".into(),
+                        state: Some(request.state.clone()),
+                    })
+                    .into_response();
+                }
+            };
+            payload = serde_json::Map::from_iter([("response".into(), response.into())]);
+        }
+
+        authorization_response(
+            &request.redirect_uri,
+            mode.transport(),
+            payload,
+            self.after_post,
+        )
     }
 }
@@ -0,0 +1,90 @@
+use axum::{http::StatusCode, Extension, Form, Json};
+
+use crate::entity::authorization::{
+    AuthorizationError, AuthorizationReference, AuthorizationRequest, PushedAuthorizationResponse,
+};
+use crate::service::baseurl::BaseUrl;
+use crate::service::cache::Cache;
+use crate::service::jsonwebtoken::JsonWebToken;
+use crate::service::oauth::Oauth;
+use crate::service::random::Random;
+
+use super::{parse_params, ApiError, ClientCredentials};
+
+/// Reads the pushed authorization request, that can also be sent as a request object.
+fn parse_request(
+    oauth: &Oauth,
+    jwt: &JsonWebToken,
+    base_url: &BaseUrl,
+    form: &[(String, String)],
+) -> Result<AuthorizationRequest, ApiError> {
+    let invalid_request = |description: String| {
+        ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: description.into(),
+            state: None,
+        })
+    };
+    let reference = parse_params::<AuthorizationReference>(form)
+        .ok()
+        .filter(AuthorizationReference::is_reference);
+    match reference {
+        Some(AuthorizationReference {
+            request_uri: Some(_),
+            ..
+        }) => Err(invalid_request(String::from(
+            "The request_uri parameter cannot be pushed.",
+        ))),
+        Some(AuthorizationReference {
+            client_id,
+            request: Some(request),
+            ..
+        }) => oauth
+            .decode_request_object(jwt, base_url.as_ref(), &client_id, &request)
+            .map_err(ApiError::bad_request),
+        _ => parse_params::<AuthorizationRequest>(form).map_err(|err| {
+            invalid_request(format!("Unable to parse the authorization request: {err}"))
+        }),
+    }
+}
+
+pub(crate) async fn handler(
+    Extension(base_url): Extension<BaseUrl>,
+    Extension(oauth): Extension<Oauth>,
+    Extension(cache): Extension<Cache>,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(random): Extension<Random>,
+    credentials: ClientCredentials,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Result<(StatusCode, Json<PushedAuthorizationResponse>), ApiError> {
+    let client_id = parse_params::<AuthorizationReference>(&form)
+        .ok()
+        .map(|reference| reference.client_id);
+    credentials.authenticate(&oauth, client_id.as_deref())?;
+    let payload = parse_request(&oauth, &jwt, &base_url, &form)?;
+    if client_id.as_deref() != Some(payload.client_id.as_str()) {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_request".into(),
+            error_description: "The client_id doesn't match the authenticated client.".into(),
+            state: Some(payload.state),
+        }));
+    }
+    oauth.check(&payload).map_err(ApiError::bad_request)?;
+
+    let request_uri = format!(
+        "{}{}",
+        AuthorizationReference::PUSHED_REQUEST_URI_PREFIX,
+        random.uuid()
+    );
+    cache
+        .insert_pushed_authorization_request(request_uri.clone(), payload)
+        .await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(PushedAuthorizationResponse {
+            request_uri,
+            expires_in: cache.pushed_authorization_request_ttl().as_secs(),
+        }),
+    ))
+}
@@ -1,19 +1,79 @@
-use axum::{extract::Path, response::Redirect, Extension};
+use axum::{
+    extract::Path,
+    response::{Html, IntoResponse, Response},
+    Extension,
+};
+use std::fmt::Write;
 use uuid::Uuid;
 
 use crate::{
-    entity::authorization::{AuthorizationError, AuthorizationRedirect, AuthorizationResponse},
-    service::{cache::Cache, database::DatabaseUser},
+    entity::authorization::{
+        AuthorizationError, AuthorizationRedirect, AuthorizationRequest, AuthorizationResponse,
+    },
+    service::{
+        cache::Cache,
+        clock::Clock,
+        consent::ConsentStore,
+        database::DatabaseUser,
+        jsonwebtoken::{AccessTokenParams, IdTokenParams},
+        random::Random,
+    },
 };
 
-use super::ApiError;
+use super::{escape_html, ApiError, MaliciousMode, Responder};
 
-pub(crate) async fn handler(
-    Extension(database): Extension<DatabaseUser>,
-    Extension(cache): Extension<Cache>,
-    Path((state, user_id)): Path<(String, Uuid)>,
-) -> Result<Redirect, ApiError> {
+fn consent_page(client_name: &str, request: &AuthorizationRequest, user_id: Uuid) -> String {
+    let scopes = request.scopes().fold(String::default(), |mut res, scope| {
+        let scope = escape_html(scope);
+        write!(
+            &mut res,
+            "<p><label><input type=\"checkbox\" name=\"scope\" value=\"{scope}\" checked /> {scope}</label></p>"
+        )
+        .unwrap();
+        res
+    });
+    let details = request.authorization_details.iter().flatten().fold(
+        String::default(),
+        |mut res, detail| {
+            let fields = serde_json::to_string_pretty(&detail.fields).unwrap_or_default();
+            write!(
+                &mut res,
+                "<h3>{}</h3><pre>{}</pre>",
+                escape_html(&detail.kind),
+                escape_html(&fields)
+            )
+            .unwrap();
+            res
+        },
+    );
+    format!(
+        "<!DOCTYPE html><html><head><title>Consent</title></head><body>\
+        <form method=\"post\" action=\"/api/consent/{}/{user_id}\">\
+        <p>{} is requesting access to your account.</p>{scopes}{details}\
+        <button type=\"submit\" name=\"decision\" value=\"approve\">Approve</button>\
+        <button type=\"submit\" name=\"decision\" value=\"deny\">Deny</button>\
+        </form></body></html>",
+        escape_html(&request.state),
+        escape_html(client_name),
+    )
+}
+
+pub(crate) async fn find_request(
+    database: &DatabaseUser,
+    cache: &Cache,
+    state: String,
+    user_id: Uuid,
+) -> Result<AuthorizationRequest, ApiError> {
     let Some(request) = cache.remove_authorization_request(&state).await else {
+        if cache.is_authorization_request_evicted(&state).await {
+            tracing::error!("authorization request {state:?} was evicted from the full cache");
+            return Err(ApiError::bad_request(AuthorizationError {
+                error: "state_evicted".into(),
+                error_description: "The authorization request was evicted, the cache being full."
+                    .into(),
+                state: Some(state),
+            }));
+        }
         return Err(ApiError::bad_request(AuthorizationError {
             error: "state_unknown".into(),
             error_description: "Unable to find authorization request with the provided state."
@@ -21,28 +81,139 @@ pub(crate) async fn handler(
             state: Some(state),
         }));
     };
-    if !database.as_ref().contains_key(&user_id) {
+    if !database.contains(&user_id) {
         return Err(ApiError::bad_request(AuthorizationError {
             error: "user_not_found".into(),
             error_description: "Unable to find the requested user.".into(),
             state: Some(state),
         }));
     };
+    Ok(request)
+}
+
+pub(crate) struct Issuer<'a> {
+    pub cache: &'a Cache,
+    pub clock: &'a Clock,
+    pub malicious: MaliciousMode,
+    pub random: &'a Random,
+    pub responder: Responder,
+}
 
-    cache
-        .insert_authorization_response(AuthorizationResponse {
-            // client_id: request.client_id,
-            code_challenge: request.code_challenge.clone(),
-            // code_challenge_method: request.code_challenge_method,
-            // redirect_uri: request.redirect_uri.clone(),
-            // response_type: request.response_type,
-            state: request.state,
-            user_id,
+impl<'a> Issuer<'a> {
+    /// Builds the authorization response matching the requested response type
+    /// and sends it back to the client.
+    pub async fn respond(
+        &self,
+        request: AuthorizationRequest,
+        user_id: Uuid,
+        scope: Option<String>,
+    ) -> Response {
+        let mut redirect = AuthorizationRedirect::new(request.state.clone());
+        // the user authenticates by being selected, or consenting to the request
+        let auth_time = self.clock.timestamp();
+        let tampering = self.malicious.tampering(&request.client_id, user_id);
+
+        if request.response_type.code {
+            let code = self.random.uuid().to_string();
+            self.cache
+                .insert_authorization_response(AuthorizationResponse {
+                    authorization_details: request.authorization_details.clone(),
+                    client_id: request.client_id.clone(),
+                    code: code.clone(),
+                    code_challenge: request.code_challenge.clone(),
+                    code_challenge_method: request.code_challenge_method.clone(),
+                    // redirect_uri: request.redirect_uri.clone(),
+                    resource: request.resource.clone(),
+                    // response_type: request.response_type,
+                    scope,
+                    state: request.state.clone(),
+                    user_id,
+                    auth_time,
+                })
+                .await;
+            redirect.code = Some(code);
+        }
+        if request.response_type.token {
+            let (access_token, _) = self.responder.jwt.encode(AccessTokenParams {
+                user_id,
+                cnf: None,
+                audience: request.resource.clone(),
+                act: None,
+                authorization_details: request.authorization_details.clone(),
+                auth_time: Some(auth_time),
+                lifetime: None,
+                claims: Default::default(),
+                tampering,
+            });
+            redirect.access_token = Some(access_token);
+            redirect.token_type = Some(String::from("Bearer"));
+            redirect.expires_in = Some(self.responder.jwt.lifetime().as_secs());
+        }
+        if request.response_type.id_token {
+            redirect.id_token = Some(self.responder.jwt.encode_id_token(IdTokenParams {
+                issuer: self.responder.base_url.as_ref(),
+                client_id: &request.client_id,
+                user_id,
+                auth_time: Some(auth_time),
+                nonce: request.nonce.as_deref(),
+                access_token: redirect.access_token.as_deref(),
+                code: redirect.code.as_deref(),
+                lifetime: None,
+                claims: Default::default(),
+                tampering,
+            }));
+        }
+
+        self.responder.send(&request, &redirect)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn handler(
+    Extension(database): Extension<DatabaseUser>,
+    Extension(cache): Extension<Cache>,
+    Extension(clock): Extension<Clock>,
+    Extension(consent): Extension<ConsentStore>,
+    Extension(random): Extension<Random>,
+    malicious: MaliciousMode,
+    responder: Responder,
+    Path((state, user_id)): Path<(String, Uuid)>,
+) -> Result<Response, ApiError> {
+    let request = find_request(&database, &cache, state, user_id).await?;
+    let client = responder.oauth.client(&request.client_id).map_err(|err| {
+        ApiError::bad_request(AuthorizationError {
+            state: Some(request.state.clone()),
+            ..err
         })
-        .await;
+    })?;
 
-    Ok(Redirect::temporary(
-        &AuthorizationRedirect::new(request.code_challenge, state)
-            .as_redirect_url(&request.redirect_uri),
-    ))
+    // authorization details describe a single transaction, so they are never remembered
+    if client.requires_consent()
+        && (request.has_prompt("consent")
+            || request.authorization_details.is_some()
+            || !consent.covers(user_id, &request.client_id, request.scopes()))
+    {
+        if request.has_prompt("none") {
+            let error = AuthorizationError {
+                error: "consent_required".into(),
+                error_description: "The user has not granted the requested scopes to this client."
+                    .into(),
+                state: Some(request.state.clone()),
+            };
+            return Ok(responder.send(&request, &error));
+        }
+        let page = consent_page(client.client_name(), &request, user_id);
+        cache.insert_authorization_request(request).await;
+        return Ok(Html(page).into_response());
+    }
+
+    let issuer = Issuer {
+        cache: &cache,
+        clock: &clock,
+        malicious,
+        random: &random,
+        responder,
+    };
+    let scope = request.scope.clone();
+    Ok(issuer.respond(request, user_id, scope).await)
 }
@@ -0,0 +1,178 @@
+use axum::{
+    extract::{rejection::JsonRejection, Path},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    Extension, Json,
+};
+
+use crate::entity::authorization::AuthorizationError;
+use crate::entity::registration::{ClientInformation, ClientMetadata};
+use crate::service::baseurl::BaseUrl;
+use crate::service::jsonwebtoken::JsonWebToken;
+use crate::service::oauth::{Client, Config, Oauth};
+use crate::service::random::Random;
+use crate::service::registration::Registration;
+
+use super::ApiError;
+
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn invalid_token(description: &'static str) -> ApiError {
+    ApiError::unauthorized(AuthorizationError {
+        error: "invalid_token".into(),
+        error_description: description.into(),
+        state: None,
+    })
+    .with_header("www-authenticate", "Bearer")
+}
+
+/// Reads the metadata, reporting the parsing errors as defined by RFC 7591.
+fn parse_metadata(
+    jwt: &JsonWebToken,
+    payload: Result<Json<ClientMetadata>, JsonRejection>,
+) -> Result<ClientMetadata, ApiError> {
+    let Json(metadata) = payload.map_err(|err| {
+        ApiError::bad_request(AuthorizationError {
+            error: "invalid_client_metadata".into(),
+            error_description: format!("Unable to parse the client metadata: {err}").into(),
+            state: None,
+        })
+    })?;
+    let valid = !metadata.redirect_uris.is_empty()
+        && metadata
+            .redirect_uris
+            .iter()
+            .all(|uri| reqwest::Url::parse(uri).is_ok_and(|url| url.fragment().is_none()));
+    if !valid {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_redirect_uri".into(),
+            error_description: "The redirect_uris must be absolute URLs without fragment.".into(),
+            state: None,
+        }));
+    }
+    if let Some(alg) = metadata.authorization_signed_response_alg {
+        if !jwt.can_sign_response(alg) {
+            return Err(ApiError::bad_request(AuthorizationError {
+                error: "invalid_client_metadata".into(),
+                error_description: format!(
+                    "The authorization responses cannot be signed with {alg:?}."
+                )
+                .into(),
+                state: None,
+            }));
+        }
+    }
+    Ok(metadata)
+}
+
+fn information(base_url: &BaseUrl, client: &Client) -> ClientInformation {
+    ClientInformation {
+        client_id: client.client_id().to_string(),
+        client_secret: client.client_secret().to_string(),
+        client_secret_expires_at: 0,
+        registration_access_token: client
+            .registration_access_token()
+            .unwrap_or_default()
+            .to_string(),
+        registration_client_uri: format!("{}/register/{}", base_url.as_ref(), client.client_id()),
+        metadata: client.metadata(),
+    }
+}
+
+/// Finds the dynamically registered client managed by the registration access token.
+fn find_client(oauth: &Oauth, client_id: &str, headers: &HeaderMap) -> Result<Client, ApiError> {
+    let token =
+        bearer(headers).ok_or_else(|| invalid_token("Missing registration access token."))?;
+    // an unknown client is reported like an invalid token, to not disclose the registered ones
+    oauth
+        .client(client_id)
+        .ok()
+        .filter(|client| client.is_registration_access_token(token))
+        .ok_or_else(|| invalid_token("The registration access token is invalid."))
+}
+
+/// Registers a client, as defined by RFC 7591.
+pub(crate) async fn register(
+    Extension(base_url): Extension<BaseUrl>,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(oauth): Extension<Oauth>,
+    Extension(random): Extension<Random>,
+    Extension(registration): Extension<Registration>,
+    headers: HeaderMap,
+    payload: Result<Json<ClientMetadata>, JsonRejection>,
+) -> Result<(StatusCode, Json<ClientInformation>), ApiError> {
+    if !registration.is_enabled() {
+        return Err(ApiError::not_found(AuthorizationError {
+            error: "not_found".into(),
+            error_description: "The dynamic client registration is disabled.".into(),
+            state: None,
+        }));
+    }
+    if !registration.authorize(bearer(&headers)) {
+        return Err(invalid_token(
+            "The initial access token is missing or invalid.",
+        ));
+    }
+    let metadata = parse_metadata(&jwt, payload)?;
+
+    let client_id = random.uuid().to_string();
+    oauth.register(Config::from_metadata(
+        client_id.clone(),
+        random.uuid().to_string(),
+        random.uuid().to_string(),
+        metadata,
+    ));
+    let client = oauth.client(&client_id).map_err(ApiError::bad_request)?;
+    Ok((StatusCode::CREATED, Json(information(&base_url, &client))))
+}
+
+/// Reads the registration of the client, as defined by RFC 7592.
+pub(crate) async fn read(
+    Extension(base_url): Extension<BaseUrl>,
+    Extension(oauth): Extension<Oauth>,
+    Path(client_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<ClientInformation>, ApiError> {
+    let client = find_client(&oauth, &client_id, &headers)?;
+    Ok(Json(information(&base_url, &client)))
+}
+
+/// Replaces the metadata of the client, keeping its credentials.
+pub(crate) async fn update(
+    Extension(base_url): Extension<BaseUrl>,
+    Extension(jwt): Extension<JsonWebToken>,
+    Extension(oauth): Extension<Oauth>,
+    Path(client_id): Path<String>,
+    headers: HeaderMap,
+    payload: Result<Json<ClientMetadata>, JsonRejection>,
+) -> Result<Json<ClientInformation>, ApiError> {
+    let client = find_client(&oauth, &client_id, &headers)?;
+    let metadata = parse_metadata(&jwt, payload)?;
+    oauth.register(Config::from_metadata(
+        client_id.clone(),
+        client.client_secret().to_string(),
+        client
+            .registration_access_token()
+            .unwrap_or_default()
+            .to_string(),
+        metadata,
+    ));
+    let client = oauth.client(&client_id).map_err(ApiError::bad_request)?;
+    Ok(Json(information(&base_url, &client)))
+}
+
+pub(crate) async fn delete(
+    Extension(oauth): Extension<Oauth>,
+    Path(client_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    find_client(&oauth, &client_id, &headers)?;
+    oauth.remove(&client_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,22 +1,31 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
 use axum::body::Body;
 use axum::extract::rejection::{FormRejection, JsonRejection};
 use axum::extract::{FromRequest, Request};
 use axum::http::header::CONTENT_TYPE;
-use axum::http::{HeaderMap, Response};
+use axum::http::{HeaderMap, Response, StatusCode};
 use axum::response::IntoResponse;
 use axum::{Extension, Form, Json};
-use axum_extra::{
-    headers::{authorization::Basic, Authorization},
-    TypedHeader,
-};
 
-use crate::entity::accesstoken::{AccessTokenRequest, AccessTokenResponse};
+use crate::entity::accesstoken::{
+    AccessTokenRequest, AccessTokenResponse, Grant, GrantType, ACCESS_TOKEN_TYPE, JWT_TOKEN_TYPE,
+};
 use crate::entity::authorization::AuthorizationError;
+use crate::entity::backchannel::{BackchannelStatus, BackchannelTokenDeliveryMode};
+use crate::service::baseurl::BaseUrl;
 use crate::service::cache::Cache;
-use crate::service::jsonwebtoken::JsonWebToken;
-use crate::service::oauth::Oauth;
+use crate::service::clock::Clock;
+use crate::service::database::DatabaseUser;
+use crate::service::jsonwebtoken::{
+    AccessTokenParams, Actor, Confirmation, JsonWebToken, JsonWebTokenClaim,
+};
+use crate::service::oauth::{Client, Oauth};
+use crate::service::random::Random;
 
-use super::ApiError;
+use super::backchannel::POLLING_INTERVAL;
+use super::{parse_params, ApiError, ClientCredentials, DpopProof, MaliciousMode};
 
 fn is_json_content(headers: &HeaderMap) -> bool {
     let Some(content_type) = headers.get(CONTENT_TYPE) else {
@@ -34,6 +43,7 @@ fn is_json_content(headers: &HeaderMap) -> bool {
 pub(crate) enum AccessTokenRequestParseError {
     Json(JsonRejection),
     Form(FormRejection),
+    Params(serde_json::Error),
 }
 
 impl IntoResponse for AccessTokenRequestParseError {
@@ -41,6 +51,9 @@ impl IntoResponse for AccessTokenRequestParseError {
         match self {
             Self::Form(inner) => inner.into_response(),
             Self::Json(inner) => inner.into_response(),
+            Self::Params(inner) => {
+                invalid_request(format!("Invalid token request: {inner}")).into_response()
+            }
         }
     }
 }
@@ -59,44 +72,445 @@ where
                 .map(|Json(inner)| inner)
                 .map_err(AccessTokenRequestParseError::Json)
         } else {
-            Form::<AccessTokenRequest>::from_request(req, state)
+            let Form(params) = Form::<Vec<(String, String)>>::from_request(req, state)
+                .await
+                .map_err(AccessTokenRequestParseError::Form)?;
+            parse_params(&params).map_err(AccessTokenRequestParseError::Params)
+        }
+    }
+}
+
+fn invalid_request(description: impl Into<Cow<'static, str>>) -> ApiError {
+    ApiError::bad_request(AuthorizationError {
+        error: "invalid_request".into(),
+        error_description: description.into(),
+        state: None,
+    })
+}
+
+fn invalid_grant(description: &'static str) -> ApiError {
+    ApiError::bad_request(AuthorizationError {
+        error: "invalid_grant".into(),
+        error_description: description.into(),
+        state: None,
+    })
+}
+
+/// Exchanges the authorization code for the grant it was issued for.
+async fn exchange_code(
+    client: &Client,
+    cache: &Cache,
+    payload: &AccessTokenRequest,
+) -> Result<Grant, ApiError> {
+    let (Some(code), Some(redirect_uri)) = (&payload.code, &payload.redirect_uri) else {
+        return Err(invalid_request(
+            "The code and redirect_uri parameters are required.",
+        ));
+    };
+    let Some(auth_response) = cache.remove_authorization_response(code).await else {
+        if cache.is_authorization_response_evicted(code).await {
+            tracing::error!("authorization code {code:?} was evicted from the full cache");
+            return Err(ApiError::bad_request(AuthorizationError {
+                error: "code_evicted".into(),
+                error_description: "The provided code was evicted, the cache being full.".into(),
+                state: None,
+            }));
+        }
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "code-not-found".into(),
+            error_description: "The provided code was not found in our database.".into(),
+            state: None,
+        }));
+    };
+
+    if !auth_response.verify_code_challenge(payload.code_verifier.as_deref()) {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_grant".into(),
+            error_description: "The code verifier doesn't match the code challenge.".into(),
+            state: Some(auth_response.state),
+        }));
+    }
+
+    if auth_response.client_id != client.client_id() {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_grant".into(),
+            error_description: "The code was issued to another client.".into(),
+            state: Some(auth_response.state),
+        }));
+    }
+    client
+        .check_redirect_uri(redirect_uri, Some(auth_response.state.clone()))
+        .map_err(ApiError::bad_request)?;
+
+    Ok(Grant {
+        act: None,
+        authorization_details: auth_response.authorization_details,
+        client_id: auth_response.client_id,
+        resource: auth_response.resource,
+        scope: auth_response.scope,
+        user_id: auth_response.user_id,
+        auth_time: Some(auth_response.auth_time),
+    })
+}
+
+/// Exchanges a backchannel authentication approved by the user for its grant, as defined by CIBA.
+async fn exchange_backchannel(
+    client: &Client,
+    cache: &Cache,
+    clock: &Clock,
+    payload: &AccessTokenRequest,
+) -> Result<Grant, ApiError> {
+    let error = |error: &'static str, description: &'static str| {
+        ApiError::bad_request(AuthorizationError {
+            error: error.into(),
+            error_description: description.into(),
+            state: None,
+        })
+    };
+    let auth_req_id = payload
+        .auth_req_id
+        .as_deref()
+        .ok_or_else(|| invalid_request("The auth_req_id parameter is required."))?;
+    let req = cache
+        .get_backchannel_authentication(auth_req_id)
+        .await
+        .filter(|req| req.client_id == client.client_id())
+        .ok_or_else(|| invalid_grant("The auth_req_id is invalid."))?;
+    if req.delivery_mode == BackchannelTokenDeliveryMode::Push {
+        return Err(error(
+            "unauthorized_client",
+            "The tokens are pushed to the client notification endpoint.",
+        ));
+    }
+    if req.is_expired(clock.now()) {
+        cache.remove_backchannel_authentication(auth_req_id).await;
+        return Err(error(
+            "expired_token",
+            "The backchannel authentication has expired.",
+        ));
+    }
+    match req.status {
+        BackchannelStatus::Pending => {
+            let now = clock.now();
+            let mut too_early = false;
+            cache
+                .update_backchannel_authentication(auth_req_id, |req| {
+                    too_early = req
+                        .last_polled_at
+                        .is_some_and(|last| last + Duration::from_secs(POLLING_INTERVAL) > now);
+                    req.last_polled_at = Some(now);
+                    req.status == BackchannelStatus::Pending
+                })
+                .await;
+            if too_early {
+                return Err(error(
+                    "slow_down",
+                    "The token endpoint is polled faster than the announced interval.",
+                ));
+            }
+            Err(error(
+                "authorization_pending",
+                "The user hasn't decided on the authentication request yet.",
+            ))
+        }
+        BackchannelStatus::Denied => {
+            cache.remove_backchannel_authentication(auth_req_id).await;
+            Err(error(
+                "access_denied",
+                "The user denied the authentication request.",
+            ))
+        }
+        BackchannelStatus::Approved => {
+            // only the poll removing the request gets the tokens
+            let req = cache
+                .remove_backchannel_authentication(auth_req_id)
                 .await
-                .map(|Form(inner)| inner)
-                .map_err(AccessTokenRequestParseError::Form)
+                .filter(|req| req.status == BackchannelStatus::Approved)
+                .ok_or_else(|| invalid_grant("The auth_req_id is invalid."))?;
+            Ok(Grant {
+                act: None,
+                authorization_details: None,
+                client_id: req.client_id,
+                resource: Vec::new(),
+                scope: req.scope,
+                user_id: req.user_id,
+                auth_time: req.auth_time,
+            })
         }
     }
 }
 
+/// Decodes a token issued by this server, given as subject or actor of a token exchange.
+fn decode_exchanged_token(
+    jwt: &JsonWebToken,
+    token: Option<&str>,
+    token_type: Option<&str>,
+    name: &str,
+) -> Result<JsonWebTokenClaim, ApiError> {
+    let token = token.ok_or_else(|| invalid_request(format!("The {name} is required.")))?;
+    if !matches!(token_type, Some(ACCESS_TOKEN_TYPE | JWT_TOKEN_TYPE)) {
+        return Err(invalid_request(format!(
+            "The {name} must be an access token or a JWT."
+        )));
+    }
+    jwt.decode(token)
+        .ok_or_else(|| invalid_grant("The exchanged token is invalid or expired."))
+}
+
+/// Builds the grant of a token exchange from the subject token and, for a delegation,
+/// the actor token, as defined by RFC 8693.
+fn exchange_token(
+    client: &Client,
+    jwt: &JsonWebToken,
+    payload: &AccessTokenRequest,
+) -> Result<Grant, ApiError> {
+    if !matches!(
+        payload.requested_token_type.as_deref(),
+        None | Some(ACCESS_TOKEN_TYPE | JWT_TOKEN_TYPE)
+    ) {
+        return Err(invalid_request(
+            "Only access tokens can be requested with a token exchange.",
+        ));
+    }
+    let subject = decode_exchanged_token(
+        jwt,
+        payload.subject_token.as_deref(),
+        payload.subject_token_type.as_deref(),
+        "subject_token",
+    )?;
+    let act = if payload.actor_token.is_some() {
+        client
+            .check_token_exchange(true)
+            .map_err(ApiError::bad_request)?;
+        let actor = decode_exchanged_token(
+            jwt,
+            payload.actor_token.as_deref(),
+            payload.actor_token_type.as_deref(),
+            "actor_token",
+        )?;
+        // the current actor comes first, followed by the prior ones
+        Some(Actor {
+            sub: actor.sub.to_string(),
+            act: subject.act.map(Box::new),
+        })
+    } else {
+        client
+            .check_token_exchange(false)
+            .map_err(ApiError::bad_request)?;
+        None
+    };
+
+    Ok(Grant {
+        act,
+        authorization_details: subject.authorization_details,
+        client_id: client.client_id().to_string(),
+        resource: Vec::new(),
+        scope: None,
+        user_id: subject.sub,
+        auth_time: subject.auth_time,
+    })
+}
+
+/// Builds the grant of a JWT bearer assertion from the user it's issued for,
+/// identified by id or email, as defined by RFC 7523.
+fn exchange_assertion(
+    client: &Client,
+    database: &DatabaseUser,
+    base_url: &BaseUrl,
+    payload: &AccessTokenRequest,
+) -> Result<Grant, ApiError> {
+    let assertion = payload
+        .assertion
+        .as_deref()
+        .ok_or_else(|| invalid_request("The assertion parameter is required."))?;
+    let audience = [
+        base_url.as_ref().to_string(),
+        format!("{}/api/token", base_url.as_ref()),
+    ];
+    let claims = client
+        .decode_assertion(&audience, assertion)
+        .map_err(ApiError::bad_request)?;
+    let user = database
+        .find(&claims.sub)
+        .ok_or_else(|| invalid_grant("The subject of the assertion doesn't match any user."))?;
+
+    Ok(Grant {
+        act: None,
+        authorization_details: None,
+        client_id: client.client_id().to_string(),
+        resource: Vec::new(),
+        // the assertion doesn't restrict the scopes, the requested ones are granted
+        scope: payload.scope.clone(),
+        user_id: user.id,
+        auth_time: None,
+    })
+}
+
+/// Restricts the grant to the authorization details, resources and scopes requested
+/// with the token request, that must have been granted before.
+fn narrow(client: &Client, grant: &Grant, payload: &AccessTokenRequest) -> Result<Grant, ApiError> {
+    let authorization_details = match &payload.authorization_details {
+        None => grant.authorization_details.clone(),
+        Some(requested) => {
+            let granted = grant.authorization_details.as_deref().unwrap_or_default();
+            if !requested.iter().all(|detail| granted.contains(detail)) {
+                return Err(ApiError::bad_request(AuthorizationError {
+                    error: "invalid_authorization_details".into(),
+                    error_description:
+                        "The requested authorization details were not granted with this code."
+                            .into(),
+                    state: None,
+                }));
+            }
+            Some(requested.clone())
+        }
+    };
+
+    let targets = payload.targets();
+    let resource = if targets.is_empty() {
+        grant.resource.clone()
+    } else if grant.resource.is_empty() {
+        // nothing was requested with the authorization, any allowed resource can be targeted
+        client
+            .check_resources(&targets)
+            .map_err(ApiError::bad_request)?;
+        targets
+    } else if targets
+        .iter()
+        .all(|resource| grant.resource.contains(resource))
+    {
+        targets
+    } else {
+        return Err(ApiError::bad_request(AuthorizationError {
+            error: "invalid_target".into(),
+            error_description: "The requested resource was not granted.".into(),
+            state: None,
+        }));
+    };
+
+    let scope = match &payload.scope {
+        None => grant.scope.clone(),
+        Some(requested) => {
+            let granted = grant.scope.as_deref().unwrap_or_default();
+            if !requested
+                .split_whitespace()
+                .all(|scope| granted.split_whitespace().any(|item| item == scope))
+            {
+                return Err(ApiError::bad_request(AuthorizationError {
+                    error: "invalid_scope".into(),
+                    error_description: "The requested scope was not granted.".into(),
+                    state: None,
+                }));
+            }
+            Some(requested.clone())
+        }
+    };
+
+    Ok(Grant {
+        act: grant.act.clone(),
+        authorization_details,
+        client_id: grant.client_id.clone(),
+        resource,
+        scope,
+        user_id: grant.user_id,
+        auth_time: grant.auth_time,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn handler(
+    Extension(base_url): Extension<BaseUrl>,
+    Extension(database): Extension<DatabaseUser>,
     Extension(oauth): Extension<Oauth>,
     Extension(cache): Extension<Cache>,
+    Extension(clock): Extension<Clock>,
     Extension(jwt): Extension<JsonWebToken>,
-    TypedHeader(Authorization(basic)): TypedHeader<Authorization<Basic>>,
+    Extension(random): Extension<Random>,
+    credentials: ClientCredentials,
+    dpop: DpopProof,
+    malicious: MaliciousMode,
     payload: AccessTokenRequest,
 ) -> Result<Json<AccessTokenResponse>, ApiError> {
-    oauth
-        .check_basic_token(basic.username(), basic.password())
-        .map_err(ApiError::bad_request)?;
+    let client = credentials.authenticate(&oauth, payload.client_id.as_deref())?;
 
-    let Some(auth_response) = cache.remove_authorization_response(&payload.code).await else {
+    if client.requires_dpop() && !dpop.is_present() {
         return Err(ApiError::bad_request(AuthorizationError {
-            error: "code-not-found".into(),
-            error_description: "The provided code was not found in our database.".into(),
+            error: "invalid_dpop_proof".into(),
+            error_description: "The client requires DPoP bound access tokens.".into(),
             state: None,
         }));
-    };
-
-    // TODO run code challenge method on code verifier
+    }
+    // the proof is checked before consuming the code so that the client can retry with a nonce
+    let jkt = dpop
+        .verify(StatusCode::BAD_REQUEST, "POST", "/api/token", None)
+        .await?;
 
-    oauth
-        .check_redirect_uri(&payload.redirect_uri, Some(auth_response.state))
-        .map_err(ApiError::bad_request)?;
+    let (grant, refresh_token) = match payload.grant_type {
+        GrantType::AuthorizationCode | GrantType::Ciba => {
+            let grant = if payload.grant_type == GrantType::Ciba {
+                exchange_backchannel(&client, &cache, &clock, &payload).await?
+            } else {
+                exchange_code(&client, &cache, &payload).await?
+            };
+            let refresh_token = random.uuid().to_string();
+            cache
+                .insert_refresh_token(refresh_token.clone(), grant.clone())
+                .await;
+            (grant, Some(refresh_token))
+        }
+        GrantType::RefreshToken => {
+            let refresh_token = payload
+                .refresh_token
+                .clone()
+                .ok_or_else(|| invalid_request("The refresh_token parameter is required."))?;
+            let grant = cache
+                .get_refresh_token(&refresh_token)
+                .await
+                .filter(|grant| grant.client_id == client.client_id())
+                .ok_or_else(|| invalid_grant("The refresh token is invalid or expired."))?;
+            (grant, Some(refresh_token))
+        }
+        GrantType::TokenExchange => (exchange_token(&client, &jwt, &payload)?, None),
+        GrantType::JwtBearer => (
+            exchange_assertion(&client, &database, &base_url, &payload)?,
+            None,
+        ),
+        GrantType::Unsupported => {
+            return Err(ApiError::bad_request(AuthorizationError {
+                error: "unsupported_grant_type".into(),
+                error_description: "The grant type is not supported.".into(),
+                state: None,
+            }));
+        }
+    };
+    let grant = narrow(&client, &grant, &payload)?;
 
-    let (access_token, expires_in) = jwt.encode(auth_response.user_id);
+    let token_type = if jkt.is_some() { "DPoP" } else { "Bearer" };
+    let x5t_s256 = credentials
+        .certificate
+        .filter(|_| client.binds_certificate())
+        .map(|certificate| certificate.thumbprint());
+    let cnf = (jkt.is_some() || x5t_s256.is_some()).then_some(Confirmation { jkt, x5t_s256 });
+    let (access_token, expires_in) = jwt.encode(AccessTokenParams {
+        user_id: grant.user_id,
+        cnf,
+        audience: grant.resource,
+        act: grant.act,
+        authorization_details: grant.authorization_details.clone(),
+        auth_time: grant.auth_time,
+        lifetime: None,
+        claims: Default::default(),
+        tampering: malicious.tampering(client.client_id(), grant.user_id),
+    });
 
     Ok(Json(AccessTokenResponse {
         access_token,
+        issued_token_type: (payload.grant_type == GrantType::TokenExchange)
+            .then_some(ACCESS_TOKEN_TYPE),
         expires_in: Some(expires_in),
-        token_type: "Bearer",
+        token_type,
+        refresh_token,
+        scope: grant.scope,
+        authorization_details: grant.authorization_details,
     }))
 }
@@ -1,29 +1,100 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode};
 use axum::{Extension, Json};
-use axum_extra::{
-    headers::{authorization::Bearer, Authorization},
-    TypedHeader,
-};
 
 use crate::entity::authorization::AuthorizationError;
 use crate::entity::user::User;
 use crate::service::database::DatabaseUser;
-use crate::service::jsonwebtoken::JsonWebToken;
+use crate::service::jsonwebtoken::{Confirmation, JsonWebToken};
+use crate::service::opaquetoken::OpaqueTokens;
+use crate::service::tls::ClientCertificate;
 
-use super::ApiError;
+use super::{ApiError, DpopProof};
+
+fn invalid_token(description: &'static str) -> ApiError {
+    ApiError::unauthorized(AuthorizationError {
+        error: "invalid_token".into(),
+        error_description: description.into(),
+        state: None,
+    })
+}
+
+/// Splits the `Authorization` header into its scheme and token.
+fn authorization(headers: &HeaderMap) -> Option<(&str, &str)> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    Some((scheme, token.trim()))
+}
 
 pub(crate) async fn handler(
     Extension(database): Extension<DatabaseUser>,
     Extension(jwt): Extension<JsonWebToken>,
-    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
+    Extension(tokens): Extension<OpaqueTokens>,
+    dpop: DpopProof,
+    certificate: Option<Extension<ClientCertificate>>,
+    headers: HeaderMap,
 ) -> Result<Json<User>, ApiError> {
-    let user_id = jwt.decode(bearer.token()).ok_or_else(|| {
-        ApiError::unauthorized(AuthorizationError {
-            error: "invalid-bearer".into(),
-            error_description: "Unable to decode bearer token.".into(),
-            state: None,
-        })
-    })?;
-    let Some(user) = database.as_ref().get(&user_id) else {
+    let Some((scheme, token)) = authorization(&headers) else {
+        return Err(
+            invalid_token("Missing access token.").with_header("www-authenticate", "Bearer, DPoP")
+        );
+    };
+    // the static and personal access tokens are never bound to a key
+    let (user_id, cnf): (_, Option<Confirmation>) = match tokens.find(token) {
+        Some(opaque) => (opaque.user_id, None),
+        None => {
+            let claims = jwt.decode(token).ok_or_else(|| {
+                ApiError::unauthorized(AuthorizationError {
+                    error: "invalid-bearer".into(),
+                    error_description: "Unable to decode bearer token.".into(),
+                    state: None,
+                })
+            })?;
+            (claims.sub, claims.cnf)
+        }
+    };
+
+    let bound_key = cnf.as_ref().and_then(|cnf| cnf.jkt.as_deref());
+    if scheme.eq_ignore_ascii_case("dpop") {
+        if !dpop.is_present() {
+            return Err(invalid_token("Missing DPoP proof.")
+                .with_header("www-authenticate", "DPoP error=\"invalid_token\""));
+        }
+        let jkt = dpop
+            .verify(
+                StatusCode::UNAUTHORIZED,
+                "GET",
+                "/api/userinfo",
+                Some(token),
+            )
+            .await?;
+        if bound_key != jkt.as_deref() {
+            return Err(
+                invalid_token("The DPoP proof key doesn't match the access token.")
+                    .with_header("www-authenticate", "DPoP error=\"invalid_token\""),
+            );
+        }
+    } else if scheme.eq_ignore_ascii_case("bearer") {
+        if bound_key.is_some() {
+            return Err(
+                invalid_token("DPoP bound access tokens can't be used as bearer tokens.")
+                    .with_header("www-authenticate", "DPoP error=\"invalid_token\""),
+            );
+        }
+    } else {
+        return Err(invalid_token("Unsupported authorization scheme.")
+            .with_header("www-authenticate", "Bearer, DPoP"));
+    }
+
+    if let Some(x5t_s256) = cnf.as_ref().and_then(|cnf| cnf.x5t_s256.as_deref()) {
+        let thumbprint = certificate.map(|Extension(certificate)| certificate.thumbprint());
+        if thumbprint.as_deref() != Some(x5t_s256) {
+            return Err(invalid_token(
+                "The access token is bound to another client certificate.",
+            ));
+        }
+    }
+
+    let Some(user) = database.get(&user_id) else {
         return Err(ApiError::bad_request(AuthorizationError {
             error: "user-not-found".into(),
             error_description: "Unable to find user.".into(),
@@ -31,5 +102,5 @@ pub(crate) async fn handler(
         }));
     };
 
-    Ok(Json(user.clone()))
+    Ok(Json(user))
 }
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Config {
+    /// Bearer token expected on the admin API.
+    pub token: String,
+}
+
+/// Guards the admin API, that is disabled when no token is configured.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Admin(Option<Arc<str>>);
+
+impl Admin {
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub fn authorize(&self, token: &str) -> bool {
+        self.0.as_deref() == Some(token)
+    }
+}
+
+impl From<Option<Config>> for Admin {
+    fn from(value: Option<Config>) -> Self {
+        Self(value.map(|config| Arc::from(config.token)))
+    }
+}
@@ -20,11 +20,12 @@ impl BaseUrl {
         Some(Self(Arc::new(std::env::var("BASE_URL").ok()?)))
     }
 
-    pub fn new(host: IpAddr, port: u16) -> Self {
-        Self(Arc::new(format!("http://{host}:{port}")))
+    pub fn new(host: IpAddr, port: u16, tls: bool) -> Self {
+        let scheme = if tls { "https" } else { "http" };
+        Self(Arc::new(format!("{scheme}://{host}:{port}")))
     }
 
-    pub fn from_env_or_new(host: IpAddr, port: u16) -> Self {
-        Self::from_env().unwrap_or_else(|| Self::new(host, port))
+    pub fn from_env_or_new(host: IpAddr, port: u16, tls: bool) -> Self {
+        Self::from_env().unwrap_or_else(|| Self::new(host, port, tls))
     }
 }
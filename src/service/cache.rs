@@ -1,50 +1,328 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
 
+use moka::notification::RemovalCause;
+use moka::ops::compute::{CompResult, Op};
+
+use crate::entity::accesstoken::Grant;
 use crate::entity::authorization::{AuthorizationRequest, AuthorizationResponse};
+use crate::entity::backchannel::{BackchannelAuthentication, BackchannelStatus};
+use crate::service::clock::Clock;
+
+/// Size and lifetime of a cache, the defaults depending on the cache.
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+pub(crate) struct CacheConfig {
+    /// Maximum number of entries, the least recently used ones being evicted first.
+    pub capacity: Option<u64>,
+    /// Lifetime of the entries, in seconds.
+    pub ttl: Option<u64>,
+}
+
+#[derive(Default, serde::Deserialize)]
+pub(crate) struct Config {
+    /// Requests waiting for the user to log in, 100 entries for 120 seconds by default.
+    #[serde(default)]
+    pub authorization_request: CacheConfig,
+    /// Codes waiting to be exchanged, 100 entries for 120 seconds by default.
+    #[serde(default)]
+    pub authorization_response: CacheConfig,
+    /// Backchannel authentication requests, the lifetime capping their requested expiry,
+    /// 100 entries for 5 minutes by default.
+    #[serde(default)]
+    pub backchannel_authentication: CacheConfig,
+    /// Requests pushed by the clients, 100 entries for 60 seconds by default.
+    #[serde(default)]
+    pub pushed_authorization_request: CacheConfig,
+    /// Refresh tokens, 1000 entries for a day by default.
+    #[serde(default)]
+    pub refresh_token: CacheConfig,
+}
+
+/// Moka cache whose entries expire with the clock, the capacity bounding the memory used.
+struct ClockCache<V> {
+    entries: moka::future::Cache<String, (V, SystemTime)>,
+    ttl: Duration,
+    /// Latest keys evicted because the cache was full, to tell them from the unknown ones.
+    evicted: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl<V: Clone + Send + Sync + 'static> ClockCache<V> {
+    fn new(name: &'static str, config: CacheConfig, capacity: u64, ttl: Duration) -> Self {
+        let capacity = config.capacity.unwrap_or(capacity);
+        let ttl = config.ttl.map(Duration::from_secs).unwrap_or(ttl);
+        let evicted = Arc::new(Mutex::new(VecDeque::new()));
+        let listener = {
+            let evicted = evicted.clone();
+            move |key: Arc<String>, _, cause| {
+                if cause != RemovalCause::Size {
+                    return;
+                }
+                tracing::warn!("{name} {key:?} evicted, the cache is full with {capacity} entries");
+                let mut evicted = evicted.lock().unwrap();
+                if evicted.len() as u64 >= capacity.max(1) {
+                    evicted.pop_front();
+                }
+                evicted.push_back(key.to_string());
+            }
+        };
+        Self {
+            entries: moka::future::Cache::builder()
+                .max_capacity(capacity)
+                .eviction_policy(moka::policy::EvictionPolicy::lru())
+                .eviction_listener(listener)
+                .build(),
+            ttl,
+            evicted,
+        }
+    }
+
+    async fn insert(&self, clock: &Clock, key: String, value: V) {
+        // a key reused after its eviction is known again
+        self.evicted.lock().unwrap().retain(|item| *item != key);
+        self.entries
+            .insert(key, (value, clock.now() + self.ttl))
+            .await;
+    }
+
+    /// Whether the missing entry was evicted because the cache was full.
+    async fn is_evicted(&self, key: &str) -> bool {
+        // the evictions are pending until the cache maintenance runs
+        self.entries.run_pending_tasks().await;
+        self.evicted.lock().unwrap().iter().any(|item| item == key)
+    }
+
+    async fn get(&self, clock: &Clock, key: &str) -> Option<V> {
+        let (value, expires_at) = self.entries.get(key).await?;
+        (expires_at > clock.now()).then_some(value)
+    }
+
+    async fn remove(&self, clock: &Clock, key: &str) -> Option<V> {
+        let (value, expires_at) = self.entries.remove(key).await?;
+        (expires_at > clock.now()).then_some(value)
+    }
+
+    /// Modifies the entry atomically, returning it when `update` accepted the change.
+    async fn update(
+        &self,
+        clock: &Clock,
+        key: &str,
+        update: impl FnOnce(&mut V) -> bool,
+    ) -> Option<V> {
+        let now = clock.now();
+        let result = self
+            .entries
+            .entry_by_ref(key)
+            .and_compute_with(|entry| {
+                let op = match entry.map(moka::Entry::into_value) {
+                    Some((mut value, expires_at)) if expires_at > now => {
+                        if update(&mut value) {
+                            Op::Put((value, expires_at))
+                        } else {
+                            Op::Nop
+                        }
+                    }
+                    _ => Op::Nop,
+                };
+                std::future::ready(op)
+            })
+            .await;
+        match result {
+            CompResult::ReplacedWith(entry) => Some(entry.into_value().0),
+            _ => None,
+        }
+    }
 
-#[derive(Clone, Default)]
+    fn values(&self, clock: &Clock) -> impl Iterator<Item = V> + '_ {
+        let now = clock.now();
+        self.entries
+            .iter()
+            .filter(move |(_, (_, expires_at))| *expires_at > now)
+            .map(|(_, (value, _))| value)
+    }
+}
+
+#[derive(Clone)]
 pub(crate) struct Cache(Arc<CacheInner>);
 
 impl Cache {
+    pub fn new(config: Config, clock: Clock) -> Self {
+        Self(Arc::new(CacheInner {
+            clock,
+            authorization_request: ClockCache::new(
+                "authorization request",
+                config.authorization_request,
+                100,
+                Duration::from_secs(120),
+            ),
+            authorization_response: ClockCache::new(
+                "authorization code",
+                config.authorization_response,
+                100,
+                Duration::from_secs(120),
+            ),
+            backchannel_authentication: ClockCache::new(
+                "backchannel authentication",
+                config.backchannel_authentication,
+                100,
+                Duration::from_secs(5 * 60),
+            ),
+            pushed_authorization_request: ClockCache::new(
+                "pushed authorization request",
+                config.pushed_authorization_request,
+                100,
+                Duration::from_secs(60),
+            ),
+            refresh_token: ClockCache::new(
+                "refresh token",
+                config.refresh_token,
+                1000,
+                Duration::from_secs(24 * 60 * 60),
+            ),
+        }))
+    }
+
+    /// Lifetime of the requests pushed by the clients, as announced in the `expires_in` field.
+    pub fn pushed_authorization_request_ttl(&self) -> Duration {
+        self.0.pushed_authorization_request.ttl
+    }
+
+    /// Maximum lifetime of the backchannel authentication requests.
+    pub fn backchannel_authentication_ttl(&self) -> Duration {
+        self.0.backchannel_authentication.ttl
+    }
+
     pub async fn insert_authorization_request(&self, req: AuthorizationRequest) {
         self.0
             .authorization_request
-            .insert(req.state.clone(), req)
+            .insert(&self.0.clock, req.state.clone(), req)
             .await;
     }
 
     pub async fn remove_authorization_request(&self, state: &str) -> Option<AuthorizationRequest> {
-        self.0.authorization_request.remove(state).await
+        self.0
+            .authorization_request
+            .remove(&self.0.clock, state)
+            .await
+    }
+
+    /// Whether the authorization request with this state was evicted because the cache was full.
+    pub async fn is_authorization_request_evicted(&self, state: &str) -> bool {
+        self.0.authorization_request.is_evicted(state).await
+    }
+
+    pub async fn insert_pushed_authorization_request(
+        &self,
+        request_uri: String,
+        req: AuthorizationRequest,
+    ) {
+        self.0
+            .pushed_authorization_request
+            .insert(&self.0.clock, request_uri, req)
+            .await;
+    }
+
+    pub async fn remove_pushed_authorization_request(
+        &self,
+        request_uri: &str,
+    ) -> Option<AuthorizationRequest> {
+        self.0
+            .pushed_authorization_request
+            .remove(&self.0.clock, request_uri)
+            .await
     }
 
     pub async fn insert_authorization_response(&self, res: AuthorizationResponse) {
         self.0
             .authorization_response
-            .insert(res.code_challenge.clone(), res)
+            .insert(&self.0.clock, res.code.clone(), res)
             .await;
     }
 
+    pub async fn get_authorization_response(&self, code: &str) -> Option<AuthorizationResponse> {
+        self.0.authorization_response.get(&self.0.clock, code).await
+    }
+
     pub async fn remove_authorization_response(&self, code: &str) -> Option<AuthorizationResponse> {
-        self.0.authorization_response.remove(code).await
+        self.0
+            .authorization_response
+            .remove(&self.0.clock, code)
+            .await
     }
-}
 
-struct CacheInner {
-    authorization_request: moka::future::Cache<String, AuthorizationRequest>,
-    authorization_response: moka::future::Cache<String, AuthorizationResponse>,
-}
+    /// Whether the code was evicted because the cache was full.
+    pub async fn is_authorization_response_evicted(&self, code: &str) -> bool {
+        self.0.authorization_response.is_evicted(code).await
+    }
 
-impl Default for CacheInner {
-    fn default() -> Self {
-        Self {
-            authorization_request: moka::future::Cache::builder()
-                .max_capacity(100)
-                .time_to_live(Duration::from_secs(120))
-                .build(),
-            authorization_response: moka::future::Cache::builder()
-                .max_capacity(100)
-                .time_to_live(Duration::from_secs(120))
-                .build(),
-        }
+    pub async fn insert_backchannel_authentication(&self, req: BackchannelAuthentication) {
+        self.0
+            .backchannel_authentication
+            .insert(&self.0.clock, req.auth_req_id.clone(), req)
+            .await;
+    }
+
+    pub async fn get_backchannel_authentication(
+        &self,
+        auth_req_id: &str,
+    ) -> Option<BackchannelAuthentication> {
+        self.0
+            .backchannel_authentication
+            .get(&self.0.clock, auth_req_id)
+            .await
+    }
+
+    /// Modifies the backchannel authentication, unless `update` declines the change.
+    pub async fn update_backchannel_authentication(
+        &self,
+        auth_req_id: &str,
+        update: impl FnOnce(&mut BackchannelAuthentication) -> bool,
+    ) -> Option<BackchannelAuthentication> {
+        self.0
+            .backchannel_authentication
+            .update(&self.0.clock, auth_req_id, update)
+            .await
+    }
+
+    pub async fn remove_backchannel_authentication(
+        &self,
+        auth_req_id: &str,
+    ) -> Option<BackchannelAuthentication> {
+        self.0
+            .backchannel_authentication
+            .remove(&self.0.clock, auth_req_id)
+            .await
+    }
+
+    /// Backchannel authentications still waiting for the decision of the user.
+    pub fn pending_backchannel_authentications(&self) -> Vec<BackchannelAuthentication> {
+        let now = self.0.clock.now();
+        self.0
+            .backchannel_authentication
+            .values(&self.0.clock)
+            .filter(|req| req.status == BackchannelStatus::Pending && !req.is_expired(now))
+            .collect()
+    }
+
+    pub async fn insert_refresh_token(&self, refresh_token: String, grant: Grant) {
+        self.0
+            .refresh_token
+            .insert(&self.0.clock, refresh_token, grant)
+            .await;
     }
+
+    pub async fn get_refresh_token(&self, refresh_token: &str) -> Option<Grant> {
+        self.0.refresh_token.get(&self.0.clock, refresh_token).await
+    }
+}
+
+struct CacheInner {
+    clock: Clock,
+    authorization_request: ClockCache<AuthorizationRequest>,
+    authorization_response: ClockCache<AuthorizationResponse>,
+    backchannel_authentication: ClockCache<BackchannelAuthentication>,
+    pushed_authorization_request: ClockCache<AuthorizationRequest>,
+    refresh_token: ClockCache<Grant>,
 }
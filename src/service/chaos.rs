@@ -0,0 +1,147 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use super::random::Random;
+
+/// Requests of the admin API, left alone by the scenarios without path so they can be switched off.
+const ADMIN_PATH: &str = "/admin/";
+
+/// Degradation of the network, applied to the requests of an endpoint or of all of them.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ChaosScenario {
+    pub name: String,
+    #[serde(default = "ChaosScenario::default_enabled")]
+    pub enabled: bool,
+    /// Path or route of the endpoint, every endpoint but the admin API when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Fixed delay before answering, in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Maximum random delay added to the fixed one, in milliseconds.
+    #[serde(default)]
+    pub jitter_ms: u64,
+    /// Probability, between 0 and 1, that the response never comes.
+    #[serde(default)]
+    pub hang_rate: f64,
+    /// Probability that the response fails with the failure status.
+    #[serde(default)]
+    pub failure_rate: f64,
+    /// Status of the failed responses, 503 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_status: Option<u16>,
+    /// Probability that the connection is dropped once the request was handled.
+    #[serde(default)]
+    pub reset_rate: f64,
+    /// Probability that the body stops halfway through.
+    #[serde(default)]
+    pub truncate_rate: f64,
+}
+
+impl ChaosScenario {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Checks that the rates are probabilities.
+    pub fn check(&self) -> Result<(), String> {
+        let rates = [
+            ("hang_rate", self.hang_rate),
+            ("failure_rate", self.failure_rate),
+            ("reset_rate", self.reset_rate),
+            ("truncate_rate", self.truncate_rate),
+        ];
+        match rates
+            .into_iter()
+            .find(|(_, rate)| !(0.0..=1.0).contains(rate))
+        {
+            Some((field, rate)) => Err(format!(
+                "The {field} {rate} of the chaos scenario {:?} isn't between 0 and 1.",
+                self.name
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn applies(&self, path: &str, route: Option<&str>) -> bool {
+        match self.path.as_deref() {
+            Some(expected) => expected == path || Some(expected) == route,
+            None => !path.starts_with(ADMIN_PATH),
+        }
+    }
+}
+
+/// Chaos scenarios, set in the configuration and switched with the admin API.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Chaos {
+    scenarios: Arc<RwLock<Vec<ChaosScenario>>>,
+    random: Random,
+}
+
+impl Chaos {
+    pub fn new(scenarios: Vec<ChaosScenario>, random: Random) -> Self {
+        if let Some(message) = scenarios.iter().find_map(|item| item.check().err()) {
+            panic!("{message}");
+        }
+        Self {
+            scenarios: Arc::new(RwLock::new(scenarios)),
+            random,
+        }
+    }
+
+    /// Whether an event with this probability happens.
+    pub fn roll(&self, rate: f64) -> bool {
+        rate > 0.0 && self.random.float() < rate
+    }
+
+    /// Delay before answering, with its random jitter.
+    pub fn delay(&self, scenario: &ChaosScenario) -> Duration {
+        let jitter = match scenario.jitter_ms {
+            0 => 0,
+            max => (self.random.float() * max.saturating_add(1) as f64) as u64,
+        };
+        Duration::from_millis(scenario.delay_ms.saturating_add(jitter))
+    }
+
+    /// First enabled scenario applying to the request.
+    pub fn scenario(&self, path: &str, route: Option<&str>) -> Option<ChaosScenario> {
+        self.scenarios
+            .read()
+            .unwrap()
+            .iter()
+            .find(|scenario| scenario.enabled && scenario.applies(path, route))
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<ChaosScenario> {
+        self.scenarios.read().unwrap().clone()
+    }
+
+    /// Adds the scenario, replacing the one with the same name.
+    pub fn put(&self, scenario: ChaosScenario) {
+        let mut scenarios = self.scenarios.write().unwrap();
+        match scenarios.iter_mut().find(|item| item.name == scenario.name) {
+            Some(existing) => *existing = scenario,
+            None => scenarios.push(scenario),
+        }
+    }
+
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Option<ChaosScenario> {
+        let mut scenarios = self.scenarios.write().unwrap();
+        let scenario = scenarios.iter_mut().find(|item| item.name == name)?;
+        scenario.enabled = enabled;
+        Some(scenario.clone())
+    }
+
+    pub fn remove(&self, name: &str) -> Option<ChaosScenario> {
+        let mut scenarios = self.scenarios.write().unwrap();
+        let index = scenarios.iter().position(|item| item.name == name)?;
+        Some(scenarios.remove(index))
+    }
+
+    pub fn clear(&self) {
+        self.scenarios.write().unwrap().clear();
+    }
+}
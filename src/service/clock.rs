@@ -0,0 +1,144 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+/// Latest time the clock can be moved to, 9999-12-31T23:59:59Z, far enough from the end
+/// of the time for the expirations computed from it not to overflow.
+pub(crate) const MAX_TIMESTAMP: u64 = 253_402_300_799;
+
+/// Whether the clock can be moved to this time.
+fn in_range(time: SystemTime) -> bool {
+    time <= SystemTime::UNIX_EPOCH + Duration::from_secs(MAX_TIMESTAMP)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ClockState {
+    /// Difference with the wall-clock time, in nanoseconds.
+    offset: i128,
+    frozen: Option<SystemTime>,
+}
+
+/// Signed difference between two times, in nanoseconds.
+fn difference(left: SystemTime, right: SystemTime) -> i128 {
+    match left.duration_since(right) {
+        Ok(duration) => duration.as_nanos() as i128,
+        Err(err) => -(err.duration().as_nanos() as i128),
+    }
+}
+
+/// Time moved by a signed difference in nanoseconds, unless it overflows.
+fn shift(time: SystemTime, offset: i128) -> Option<SystemTime> {
+    let nanos = offset.unsigned_abs();
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    let duration = Duration::new(secs, (nanos % 1_000_000_000) as u32);
+    if offset < 0 {
+        time.checked_sub(duration)
+    } else {
+        time.checked_add(duration)
+    }
+}
+
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// State of the clock, as shown by the admin API.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ClockStatus {
+    /// Current time, as UTC timestamp.
+    pub now: u64,
+    pub frozen: bool,
+    /// Difference with the wall-clock time, in seconds.
+    pub offset: i64,
+}
+
+/// Time used for the expiry of the tokens, codes and requests, that can be frozen or moved.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Clock(Arc<RwLock<ClockState>>);
+
+impl Clock {
+    /// Clock stopped at the given time.
+    pub fn frozen(now: SystemTime) -> Self {
+        Self(Arc::new(RwLock::new(ClockState {
+            offset: 0,
+            frozen: Some(now),
+        })))
+    }
+
+    pub fn now(&self) -> SystemTime {
+        let state = *self.0.read().unwrap();
+        state.frozen.unwrap_or_else(|| {
+            shift(SystemTime::now(), state.offset).expect("the offset is checked when changed")
+        })
+    }
+
+    /// Current time, as UTC timestamp.
+    pub fn timestamp(&self) -> u64 {
+        timestamp(self.now())
+    }
+
+    pub fn status(&self) -> ClockStatus {
+        let now = self.now();
+        ClockStatus {
+            now: timestamp(now),
+            frozen: self.0.read().unwrap().frozen.is_some(),
+            offset: (difference(now, SystemTime::now()) / 1_000_000_000) as i64,
+        }
+    }
+
+    /// Stops the time, until the clock is unfrozen.
+    pub fn freeze(&self) {
+        let now = self.now();
+        self.0.write().unwrap().frozen = Some(now);
+    }
+
+    /// Lets the time flow again, from where it was frozen.
+    pub fn unfreeze(&self) {
+        let mut state = self.0.write().unwrap();
+        if let Some(frozen) = state.frozen.take() {
+            state.offset = difference(frozen, SystemTime::now());
+        }
+    }
+
+    /// Moves the clock forward, unless it goes past the latest time.
+    pub fn advance(&self, duration: Duration) -> bool {
+        let mut state = self.0.write().unwrap();
+        match state.frozen {
+            Some(frozen) => match frozen.checked_add(duration).filter(|time| in_range(*time)) {
+                Some(frozen) => state.frozen = Some(frozen),
+                None => return false,
+            },
+            None => {
+                let offset = state.offset.checked_add(duration.as_nanos() as i128);
+                let valid = |offset: &i128| shift(SystemTime::now(), *offset).is_some_and(in_range);
+                match offset.filter(valid) {
+                    Some(offset) => state.offset = offset,
+                    None => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// Moves the clock to the given time, keeping it frozen if it was,
+    /// unless it is past the latest time.
+    pub fn set(&self, now: SystemTime) -> bool {
+        if !in_range(now) {
+            return false;
+        }
+        let mut state = self.0.write().unwrap();
+        match state.frozen.as_mut() {
+            Some(frozen) => *frozen = now,
+            None => state.offset = difference(now, SystemTime::now()),
+        }
+        true
+    }
+
+    /// Goes back to the wall-clock time.
+    pub fn reset(&self) {
+        *self.0.write().unwrap() = ClockState::default();
+    }
+}
@@ -0,0 +1,36 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use uuid::Uuid;
+
+type GrantedScopes = HashMap<(Uuid, String), HashSet<String>>;
+
+/// Scopes granted by each user to each client, remembered until the server restarts.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConsentStore(Arc<RwLock<GrantedScopes>>);
+
+impl ConsentStore {
+    /// Returns `true` when the user already granted every requested scope to the client.
+    pub fn covers<'a>(
+        &self,
+        user_id: Uuid,
+        client_id: &str,
+        mut scopes: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        let store = self.0.read().unwrap();
+        store
+            .get(&(user_id, client_id.to_string()))
+            .map(|granted| scopes.all(|scope| granted.contains(scope)))
+            .unwrap_or(false)
+    }
+
+    pub fn grant<'a>(&self, user_id: Uuid, client_id: &str, scopes: impl Iterator<Item = &'a str>) {
+        let mut store = self.0.write().unwrap();
+        store
+            .entry((user_id, client_id.to_string()))
+            .or_default()
+            .extend(scopes.map(String::from));
+    }
+}
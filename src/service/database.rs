@@ -1,22 +1,60 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
 use uuid::Uuid;
 
 use crate::entity::user::User;
 
+/// Users seeded from the configuration, that can be changed through the admin API.
 #[derive(Clone, Debug)]
-pub(crate) struct DatabaseUser(Arc<HashMap<Uuid, User>>);
+pub(crate) struct DatabaseUser(Arc<RwLock<HashMap<Uuid, User>>>);
 
-impl AsRef<HashMap<Uuid, User>> for DatabaseUser {
-    fn as_ref(&self) -> &HashMap<Uuid, User> {
-        &self.0
+impl DatabaseUser {
+    pub fn get(&self, id: &Uuid) -> Option<User> {
+        self.0.read().unwrap().get(id).cloned()
+    }
+
+    pub fn contains(&self, id: &Uuid) -> bool {
+        self.0.read().unwrap().contains_key(id)
+    }
+
+    /// Finds the user by id or by email.
+    pub fn find(&self, hint: &str) -> Option<User> {
+        match hint.parse::<Uuid>() {
+            Ok(id) => self.get(&id),
+            Err(_) => self
+                .0
+                .read()
+                .unwrap()
+                .values()
+                .find(|user| user.email == hint)
+                .cloned(),
+        }
+    }
+
+    /// Lists the users, sorted by name.
+    pub fn list(&self) -> Vec<User> {
+        let mut users = self.0.read().unwrap().values().cloned().collect::<Vec<_>>();
+        users.sort_by(|first, second| first.name.cmp(&second.name));
+        users
+    }
+
+    /// Inserts or replaces the user, returning the previous one.
+    pub fn insert(&self, user: User) -> Option<User> {
+        self.0.write().unwrap().insert(user.id, user)
+    }
+
+    pub fn remove(&self, id: &Uuid) -> Option<User> {
+        self.0.write().unwrap().remove(id)
     }
 }
 
 impl From<Vec<User>> for DatabaseUser {
     fn from(value: Vec<User>) -> Self {
-        Self(Arc::new(HashMap::from_iter(
+        Self(Arc::new(RwLock::new(HashMap::from_iter(
             value.into_iter().map(|item| (item.id, item)),
-        )))
+        ))))
     }
 }
@@ -0,0 +1,199 @@
+use std::{
+    borrow::Cow,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use base64::Engine;
+use sha2::Digest;
+
+use crate::entity::authorization::AuthorizationError;
+use crate::service::random::Random;
+
+/// Maximum difference between the `iat` of a proof and the current time.
+const PROOF_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+/// Lifetime of the nonces issued by the server.
+const NONCE_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Config {
+    /// Require the proofs to contain a nonce issued by the server.
+    #[serde(default = "Config::default_require_nonce")]
+    pub require_nonce: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            require_nonce: Self::default_require_nonce(),
+        }
+    }
+}
+
+impl Config {
+    fn default_require_nonce() -> bool {
+        true
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ProofClaim {
+    jti: String,
+    htm: String,
+    htu: String,
+    iat: u64,
+    ath: Option<String>,
+    nonce: Option<String>,
+}
+
+fn invalid_proof(description: impl Into<Cow<'static, str>>) -> AuthorizationError {
+    AuthorizationError {
+        error: "invalid_dpop_proof".into(),
+        error_description: description.into(),
+        state: None,
+    }
+}
+
+fn sha256(value: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(sha2::Sha256::digest(value))
+}
+
+/// JWK thumbprint, as defined by RFC 7638.
+fn thumbprint(jwk: &jsonwebtoken::jwk::Jwk) -> Option<String> {
+    let value = serde_json::to_value(jwk).ok()?;
+    let members: &[&str] = match value["kty"].as_str()? {
+        "EC" => &["crv", "kty", "x", "y"],
+        "RSA" => &["e", "kty", "n"],
+        "OKP" => &["crv", "kty", "x"],
+        _ => return None,
+    };
+    // the members are listed in lexicographic order, as required for the thumbprint
+    let required = members
+        .iter()
+        .map(|name| {
+            let member = serde_json::Value::from(value.get(*name)?.as_str()?);
+            Some(format!("\"{name}\":{member}"))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(sha256(format!("{{{}}}", required.join(",")).as_bytes()))
+}
+
+struct DpopInner {
+    require_nonce: bool,
+    random: Random,
+    nonces: moka::future::Cache<String, ()>,
+    proofs: moka::future::Cache<String, ()>,
+}
+
+/// Validates DPoP proofs, as defined by RFC 9449.
+#[derive(Clone)]
+pub(crate) struct Dpop(Arc<DpopInner>);
+
+impl Dpop {
+    pub fn new(value: Config, random: Random) -> Self {
+        Self(Arc::new(DpopInner {
+            require_nonce: value.require_nonce,
+            random,
+            nonces: moka::future::Cache::builder()
+                .max_capacity(1000)
+                .time_to_live(NONCE_TTL)
+                .build(),
+            proofs: moka::future::Cache::builder()
+                .max_capacity(10_000)
+                .time_to_live(PROOF_MAX_AGE * 2)
+                .build(),
+        }))
+    }
+
+    pub async fn issue_nonce(&self) -> String {
+        let nonce = self.0.random.uuid().simple().to_string();
+        self.0.nonces.insert(nonce.clone(), ()).await;
+        nonce
+    }
+
+    /// Verifies the proof for the given request and returns the thumbprint of its key.
+    ///
+    /// The access token has to be provided when the proof is sent to a protected resource.
+    pub async fn verify(
+        &self,
+        proof: &str,
+        method: &str,
+        url: &str,
+        access_token: Option<&str>,
+    ) -> Result<String, AuthorizationError> {
+        use jsonwebtoken::Algorithm;
+
+        let header = jsonwebtoken::decode_header(proof)
+            .map_err(|err| invalid_proof(format!("Unable to decode the proof: {err}")))?;
+        if header.typ.as_deref() != Some("dpop+jwt") {
+            return Err(invalid_proof("The proof must be of type dpop+jwt."));
+        }
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(invalid_proof("The proof must use an asymmetric algorithm."));
+        }
+        let jwk = header
+            .jwk
+            .as_ref()
+            .ok_or_else(|| invalid_proof("The proof must contain the public key."))?;
+        let jkt = thumbprint(jwk).ok_or_else(|| invalid_proof("Unsupported proof key."))?;
+        let key = jsonwebtoken::DecodingKey::from_jwk(jwk)
+            .map_err(|err| invalid_proof(format!("Invalid proof key: {err}")))?;
+
+        let mut validation = jsonwebtoken::Validation::new(header.alg);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        let claims = jsonwebtoken::decode::<ProofClaim>(proof, &key, &validation)
+            .map_err(|err| invalid_proof(format!("Invalid proof: {err}")))?
+            .claims;
+
+        if !claims.htm.eq_ignore_ascii_case(method) {
+            return Err(invalid_proof(
+                "The proof htm doesn't match the request method.",
+            ));
+        }
+        let htu = claims.htu.split(['?', '#']).next().unwrap_or_default();
+        if htu != url {
+            return Err(invalid_proof(
+                "The proof htu doesn't match the request url.",
+            ));
+        }
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if now.abs_diff(claims.iat) > PROOF_MAX_AGE.as_secs() {
+            return Err(invalid_proof(
+                "The proof iat is too far from the current time.",
+            ));
+        }
+        if let Some(access_token) = access_token {
+            if claims.ath.as_deref() != Some(sha256(access_token.as_bytes()).as_str()) {
+                return Err(invalid_proof(
+                    "The proof ath doesn't match the access token.",
+                ));
+            }
+        }
+        let nonce_valid = match claims.nonce.as_deref() {
+            Some(nonce) => self.0.nonces.contains_key(nonce),
+            None => !self.0.require_nonce,
+        };
+        if !nonce_valid {
+            return Err(AuthorizationError {
+                error: "use_dpop_nonce".into(),
+                error_description: "The proof must contain a nonce issued by the server.".into(),
+                state: None,
+            });
+        }
+
+        let replay_key = format!("{jkt}:{}", claims.jti);
+        let entry = self.0.proofs.entry(replay_key).or_insert(()).await;
+        if !entry.is_fresh() {
+            return Err(invalid_proof("The proof has already been used."));
+        }
+
+        Ok(jkt)
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::{Arc, RwLock};
+
+use uuid::Uuid;
+
+use super::random::Random;
+
+/// Requests of the admin API, never failed so that the rules can always be removed.
+const ADMIN_PATH: &str = "/admin/";
+
+/// Makes an endpoint fail, for the requests matching all of its criteria.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct FaultRule {
+    /// Identifier given when the rule is added.
+    #[serde(default, skip_deserializing)]
+    pub id: Uuid,
+    /// Path of the endpoint, like `/api/token`, or its route, like `/api/redirect/:state/:user_id`.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<Uuid>,
+    /// Number of matching requests let through before failing.
+    #[serde(default)]
+    pub after: u32,
+    /// Number of requests to fail, all of them when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<u32>,
+    /// HTTP status of the response, 400 with an error and 500 without.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+    /// Raw body sent instead of the error, to simulate malformed responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Number of requests that matched the rule so far.
+    #[serde(default, skip_deserializing)]
+    pub matched: u32,
+}
+
+impl FaultRule {
+    fn targets(&self, path: &str, route: Option<&str>) -> bool {
+        !path.starts_with(ADMIN_PATH) && (self.path == path || route == Some(self.path.as_str()))
+    }
+
+    fn matches(
+        &self,
+        path: &str,
+        route: Option<&str>,
+        client_id: Option<&str>,
+        user_id: Option<Uuid>,
+    ) -> bool {
+        self.targets(path, route)
+            && self
+                .client_id
+                .as_deref()
+                .is_none_or(|expected| client_id == Some(expected))
+            && self
+                .user_id
+                .is_none_or(|expected| user_id == Some(expected))
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.times
+            .is_some_and(|times| self.matched.saturating_sub(self.after) >= times)
+    }
+}
+
+/// Fault injection rules, set in the configuration or with the admin API.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Faults {
+    rules: Arc<RwLock<Vec<FaultRule>>>,
+    random: Random,
+}
+
+impl Faults {
+    pub fn new(rules: Vec<FaultRule>, random: Random) -> Self {
+        let faults = Self {
+            rules: Arc::default(),
+            random,
+        };
+        for rule in rules {
+            faults.add(rule);
+        }
+        faults
+    }
+
+    /// Whether a rule may apply to the endpoint, before looking for the client and the user.
+    pub fn targets(&self, path: &str, route: Option<&str>) -> bool {
+        self.rules
+            .read()
+            .unwrap()
+            .iter()
+            .any(|rule| rule.targets(path, route) && !rule.is_exhausted())
+    }
+
+    /// Counts the request for every matching rule, returning the first one that fails it.
+    pub fn check(
+        &self,
+        path: &str,
+        route: Option<&str>,
+        client_id: Option<&str>,
+        user_id: Option<Uuid>,
+    ) -> Option<FaultRule> {
+        let mut rules = self.rules.write().unwrap();
+        let mut fault = None;
+        for rule in rules.iter_mut() {
+            if rule.is_exhausted() || !rule.matches(path, route, client_id, user_id) {
+                continue;
+            }
+            rule.matched += 1;
+            if fault.is_none() && rule.matched > rule.after {
+                fault = Some(rule.clone());
+            }
+        }
+        fault
+    }
+
+    pub fn list(&self) -> Vec<FaultRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Adds the rule, returning it with its identifier.
+    pub fn add(&self, mut rule: FaultRule) -> FaultRule {
+        rule.id = self.random.uuid();
+        self.rules.write().unwrap().push(rule.clone());
+        rule
+    }
+
+    pub fn remove(&self, id: Uuid) -> Option<FaultRule> {
+        let mut rules = self.rules.write().unwrap();
+        let index = rules.iter().position(|rule| rule.id == id)?;
+        Some(rules.remove(index))
+    }
+
+    pub fn clear(&self) {
+        self.rules.write().unwrap().clear();
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Config {
+    /// Number of requests kept, the oldest ones are dropped first.
+    #[serde(default = "Config::default_capacity")]
+    pub capacity: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            capacity: Self::default_capacity(),
+        }
+    }
+}
+
+impl Config {
+    fn default_capacity() -> usize {
+        1000
+    }
+}
+
+/// Request handled by the router, with the response it got.
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct JournalEntry {
+    pub id: u64,
+    /// Reception time of the request, as UTC timestamp in milliseconds.
+    pub timestamp: u64,
+    pub method: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Client identified by the `client_id` parameter or the HTTP Basic credentials.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    pub request_headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub request_body: String,
+    pub status: u16,
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub response_body: String,
+    pub duration_ms: u64,
+}
+
+/// Criteria of the entries to return, all of them being optional.
+#[derive(Debug, Default, serde::Deserialize)]
+pub(crate) struct JournalFilter {
+    pub path: Option<String>,
+    pub client_id: Option<String>,
+    /// Earliest timestamp, in milliseconds.
+    pub since: Option<u64>,
+    /// Latest timestamp, in milliseconds.
+    pub until: Option<u64>,
+}
+
+impl JournalFilter {
+    fn matches(&self, entry: &JournalEntry) -> bool {
+        self.path.as_ref().is_none_or(|path| &entry.path == path)
+            && self
+                .client_id
+                .as_ref()
+                .is_none_or(|client_id| entry.client_id.as_ref() == Some(client_id))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+struct JournalInner {
+    capacity: usize,
+    next_id: AtomicU64,
+    entries: RwLock<VecDeque<JournalEntry>>,
+}
+
+/// Bounded record of the requests handled by the router, to assert what the clients sent.
+#[derive(Clone)]
+pub(crate) struct Journal(Arc<JournalInner>);
+
+impl From<Config> for Journal {
+    fn from(value: Config) -> Self {
+        Self(Arc::new(JournalInner {
+            capacity: value.capacity,
+            next_id: AtomicU64::new(1),
+            entries: RwLock::new(VecDeque::with_capacity(value.capacity)),
+        }))
+    }
+}
+
+impl Journal {
+    pub fn next_id(&self) -> u64 {
+        self.0.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn record(&self, entry: JournalEntry) {
+        if self.0.capacity == 0 {
+            return;
+        }
+        let mut entries = self.0.entries.write().unwrap();
+        while entries.len() >= self.0.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Entries matching the filter, from the oldest to the latest.
+    pub fn list(&self, filter: &JournalFilter) -> Vec<JournalEntry> {
+        self.0
+            .entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.0.entries.write().unwrap().clear();
+    }
+}
@@ -3,12 +3,107 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+use base64::Engine;
+use sha2::Digest;
 use uuid::Uuid;
 
+use crate::entity::authorization::{deserialize_one_or_many, AuthorizationDetail};
+use crate::service::clock::Clock;
+use crate::service::random::Random;
+
+/// Key identifier of the RSA key in the published key set.
+const RSA_KEY_ID: &str = "rsa";
+/// Type of the access tokens, telling them from the ID tokens as defined by RFC 9068.
+const ACCESS_TOKEN_TYPE: &str = "at+jwt";
+
+/// Lifetime of the JWT secured authorization responses.
+const RESPONSE_DURATION: Duration = Duration::from_secs(10 * 60);
+
+/// Issuer and audience of the tampered tokens.
+const MALICIOUS_PARTY: &str = "https://malicious.example";
+/// Size of the padding claim of the oversized tokens.
+const OVERSIZED_PADDING: usize = 64 * 1024;
+/// Secret of the tokens with an invalid signature.
+const FORGED_SECRET: &[u8] = b"not the signing secret";
+
+/// Flaw deliberately put in an issued token, to check that the relying parties reject it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Tampering {
+    InvalidSignature,
+    AlgNone,
+    /// Signed with HS256 using the RSA public key as secret, or claiming RS256 with an HMAC
+    /// signature when no RSA key is configured.
+    KeyConfusion,
+    WrongIssuer,
+    WrongAudience,
+    Expired,
+    NotYetValid,
+    MissingSub,
+    Oversized,
+    /// Only alters the ID tokens.
+    WrongNonce,
+    /// Only alters the ID tokens.
+    WrongAtHash,
+}
+
+impl std::str::FromStr for Tampering {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::from(value))
+            .map_err(|_| format!("unknown tampering {value:?}"))
+    }
+}
+
+impl Tampering {
+    /// Alters the claims, before they are signed, `now` being a UTC timestamp.
+    fn apply(
+        self,
+        claims: &mut serde_json::Map<String, serde_json::Value>,
+        id_token: bool,
+        now: u64,
+        random: &Random,
+    ) {
+        match self {
+            Self::WrongIssuer => {
+                claims.insert("iss".into(), MALICIOUS_PARTY.into());
+            }
+            Self::WrongAudience => {
+                claims.insert("aud".into(), MALICIOUS_PARTY.into());
+            }
+            Self::Expired => {
+                claims.insert("exp".into(), now.saturating_sub(60 * 60).into());
+            }
+            Self::NotYetValid => {
+                claims.insert("nbf".into(), (now + 60 * 60).into());
+            }
+            Self::MissingSub => {
+                claims.remove("sub");
+            }
+            Self::Oversized => {
+                claims.insert("padding".into(), "x".repeat(OVERSIZED_PADDING).into());
+            }
+            Self::WrongNonce if id_token => {
+                claims.insert("nonce".into(), random.uuid().to_string().into());
+            }
+            Self::WrongAtHash if id_token => {
+                claims.insert(
+                    "at_hash".into(),
+                    half_hash(&random.uuid().to_string()).into(),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
 #[derive(serde::Deserialize)]
 pub(crate) struct Config {
     pub duration: Option<u64>,
     pub secret: String,
+    /// PEM encoded RSA private key, required to sign with the RS* and PS* algorithms.
+    pub rsa_private_key: Option<String>,
 }
 
 #[cfg(test)]
@@ -17,29 +112,169 @@ impl Default for Config {
         Self {
             duration: None,
             secret: String::from("secret"),
+            rsa_private_key: None,
         }
     }
 }
 
+/// Key the access token is bound to, as defined by RFC 7800.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Confirmation {
+    /// Thumbprint of the DPoP proof key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jkt: Option<String>,
+    /// Thumbprint of the TLS client certificate.
+    #[serde(default, rename = "x5t#S256", skip_serializing_if = "Option::is_none")]
+    pub x5t_s256: Option<String>,
+}
+
+/// Party acting on behalf of the subject, with the prior actors of the delegation chain
+/// nested in its own `act` member, as defined by RFC 8693.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub(crate) struct Actor {
+    pub sub: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Box<Actor>>,
+}
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
-struct JsonWebTokenClaim {
-    exp: usize, // Required (validate_exp defaults to true in validation). Expiration time (as UTC timestamp)
-    sub: Uuid,  // Optional. Subject (whom token refers to)
+pub(crate) struct JsonWebTokenClaim {
+    pub exp: usize, // Required, checked against the clock when decoding. Expiration time (as UTC timestamp)
+    pub sub: Uuid,  // Optional. Subject (whom token refers to)
+    /// Time the token was issued at, as UTC timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<Uuid>,
+    /// Resources the token is restricted to.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_one_or_many",
+        serialize_with = "serialize_one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub aud: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<Confirmation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
+    /// Time the user authenticated, as UTC timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_time: Option<u64>,
+    /// Additional claims, like the ones of the minted tokens.
+    #[serde(flatten)]
+    pub claims: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Serializes a single audience as a string, as most resource servers expect.
+fn serialize_one_or_many<S: serde::Serializer>(
+    values: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match values {
+        [value] => serializer.serialize_str(value),
+        values => serde::Serialize::serialize(values, serializer),
+    }
 }
 
-impl JsonWebTokenClaim {
-    pub fn new(user_id: Uuid, expiration: Duration) -> Self {
+pub(crate) struct AccessTokenParams {
+    pub user_id: Uuid,
+    pub cnf: Option<Confirmation>,
+    pub audience: Vec<String>,
+    pub act: Option<Actor>,
+    pub authorization_details: Option<Vec<AuthorizationDetail>>,
+    /// Time the user authenticated, as UTC timestamp.
+    pub auth_time: Option<u64>,
+    /// Overrides the configured lifetime of the token.
+    pub lifetime: Option<Duration>,
+    pub claims: serde_json::Map<String, serde_json::Value>,
+    pub tampering: Option<Tampering>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct IdTokenClaim<'a> {
+    iss: &'a str,
+    sub: Uuid,
+    aud: &'a str,
+    exp: u64,
+    iat: u64,
+    jti: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    at_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    c_hash: Option<String>,
+    #[serde(flatten)]
+    claims: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Left-most half of the hash of the value, as used by `at_hash` and `c_hash`.
+fn half_hash(value: &str) -> String {
+    // tokens are signed with HS512, so the hash has to be SHA-512
+    let digest = sha2::Sha512::digest(value.as_bytes());
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&digest[..digest.len() / 2])
+}
+
+pub(crate) struct IdTokenParams<'a> {
+    pub issuer: &'a str,
+    pub client_id: &'a str,
+    pub user_id: Uuid,
+    /// Time the user authenticated, as UTC timestamp.
+    pub auth_time: Option<u64>,
+    pub nonce: Option<&'a str>,
+    pub access_token: Option<&'a str>,
+    pub code: Option<&'a str>,
+    /// Overrides the configured lifetime of the token.
+    pub lifetime: Option<Duration>,
+    pub claims: serde_json::Map<String, serde_json::Value>,
+    pub tampering: Option<Tampering>,
+}
+
+struct RsaKey {
+    private_key: rsa::RsaPrivateKey,
+    encoding_key: jsonwebtoken::EncodingKey,
+    public_pem: String,
+    modulus: String,
+    exponent: String,
+}
+
+impl RsaKey {
+    fn from_pem(pem: &str) -> Self {
+        use rsa::pkcs1::{DecodeRsaPrivateKey, EncodeRsaPrivateKey};
+        use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey, LineEnding};
+        use rsa::traits::PublicKeyParts;
+
+        let key = rsa::RsaPrivateKey::from_pkcs1_pem(pem)
+            .or_else(|_| rsa::RsaPrivateKey::from_pkcs8_pem(pem))
+            .expect("couldn't parse rsa private key");
+        let der = key.to_pkcs1_der().expect("couldn't encode rsa private key");
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
         Self {
-            exp: expiration.as_secs() as usize,
-            sub: user_id,
+            encoding_key: jsonwebtoken::EncodingKey::from_rsa_der(der.as_bytes()),
+            public_pem: key
+                .to_public_key()
+                .to_public_key_pem(LineEnding::LF)
+                .expect("couldn't encode rsa public key"),
+            modulus: engine.encode(key.n().to_bytes_be()),
+            exponent: engine.encode(key.e().to_bytes_be()),
+            private_key: key,
         }
     }
 }
 
 struct JsonWebTokenInner {
+    clock: Clock,
+    random: Random,
     duration: Duration,
+    rsa: Option<RsaKey>,
     decoding_key: jsonwebtoken::DecodingKey,
     encoding_key: jsonwebtoken::EncodingKey,
+    access_token_header: jsonwebtoken::Header,
     header: jsonwebtoken::Header,
     validation: jsonwebtoken::Validation,
 }
@@ -49,39 +284,339 @@ pub(crate) struct JsonWebToken(Arc<JsonWebTokenInner>);
 
 impl From<Config> for JsonWebToken {
     fn from(value: Config) -> Self {
+        Self::new(value, Clock::default(), Random::default())
+    }
+}
+
+impl JsonWebToken {
+    pub fn new(value: Config, clock: Clock, random: Random) -> Self {
+        // the audience is checked by the resource servers, not when decoding the tokens
+        let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS512);
+        validation.validate_aud = false;
+        // the expiration is checked against the clock, that may not be the system one
+        validation.validate_exp = false;
+
         Self(Arc::new(JsonWebTokenInner {
+            clock,
+            random,
             duration: Duration::from_secs(value.duration.unwrap_or(60 * 60)),
+            rsa: value.rsa_private_key.as_deref().map(RsaKey::from_pem),
             decoding_key: jsonwebtoken::DecodingKey::from_secret(value.secret.as_bytes()),
             encoding_key: jsonwebtoken::EncodingKey::from_secret(value.secret.as_bytes()),
+            access_token_header: jsonwebtoken::Header {
+                typ: Some(String::from(ACCESS_TOKEN_TYPE)),
+                ..jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS512)
+            },
             header: jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS512),
-            validation: jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS512),
+            validation,
         }))
     }
-}
 
-impl JsonWebToken {
-    pub fn encode(&self, user_id: Uuid) -> (String, u64) {
-        use std::ops::Add;
+    /// How long the access tokens live unless told otherwise.
+    pub fn lifetime(&self) -> Duration {
+        self.0.duration
+    }
 
-        let expiration = SystemTime::now()
-            .add(self.0.duration)
+    /// Whether the expiration of a token living this long can be represented.
+    pub fn accepts_lifetime(&self, lifetime: Duration) -> bool {
+        self.0.clock.now().checked_add(lifetime).is_some()
+    }
+
+    pub fn encode(&self, params: AccessTokenParams) -> (String, u64) {
+        let now = self
+            .0
+            .clock
+            .now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap();
+        let expiration = now + params.lifetime.unwrap_or(self.0.duration);
 
-        let claim = JsonWebTokenClaim::new(user_id, expiration);
-        (
-            jsonwebtoken::encode(&self.0.header, &claim, &self.0.encoding_key).unwrap(),
-            expiration.as_secs(),
-        )
+        let claim = JsonWebTokenClaim {
+            exp: expiration.as_secs() as usize,
+            sub: params.user_id,
+            iat: Some(now.as_secs()),
+            jti: Some(self.0.random.uuid()),
+            aud: params.audience,
+            act: params.act,
+            cnf: params.cnf,
+            authorization_details: params.authorization_details,
+            auth_time: params.auth_time,
+            claims: params.claims,
+        };
+        let token = match params.tampering {
+            Some(tampering) => self.tamper(&claim, tampering, false),
+            None => jsonwebtoken::encode(&self.0.access_token_header, &claim, &self.0.encoding_key)
+                .unwrap(),
+        };
+        (token, expiration.as_secs())
     }
 
-    pub fn decode(&self, token: &str) -> Option<Uuid> {
+    pub fn encode_id_token(&self, params: IdTokenParams<'_>) -> String {
+        let now = self
+            .0
+            .clock
+            .now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+
+        let claim = IdTokenClaim {
+            iss: params.issuer,
+            sub: params.user_id,
+            aud: params.client_id,
+            exp: (now + params.lifetime.unwrap_or(self.0.duration)).as_secs(),
+            iat: now.as_secs(),
+            jti: self.0.random.uuid(),
+            auth_time: params.auth_time,
+            nonce: params.nonce,
+            at_hash: params.access_token.map(half_hash),
+            c_hash: params.code.map(half_hash),
+            claims: params.claims,
+        };
+        match params.tampering {
+            Some(tampering) => self.tamper(&claim, tampering, true),
+            None => jsonwebtoken::encode(&self.0.header, &claim, &self.0.encoding_key).unwrap(),
+        }
+    }
+
+    /// Signs the claims with the flaw of the tampering.
+    fn tamper<T: serde::Serialize>(
+        &self,
+        claims: &T,
+        tampering: Tampering,
+        id_token: bool,
+    ) -> String {
+        use jsonwebtoken::{Algorithm, EncodingKey};
+
+        let serde_json::Value::Object(mut claims) = serde_json::to_value(claims).unwrap() else {
+            unreachable!("the claims are serialized as objects");
+        };
+        tampering.apply(
+            &mut claims,
+            id_token,
+            self.0.clock.timestamp(),
+            &self.0.random,
+        );
+
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let payload = engine.encode(serde_json::to_vec(&claims).unwrap());
+        // the header is built by hand, as the library doesn't produce these combinations
+        let typ = if id_token { "JWT" } else { ACCESS_TOKEN_TYPE };
+        let unsigned = |alg: &str, kid: Option<&str>| {
+            let header = serde_json::json!({ "alg": alg, "typ": typ, "kid": kid });
+            let mut header = header.as_object().cloned().unwrap_or_default();
+            header.retain(|_, value| !value.is_null());
+            format!(
+                "{}.{payload}",
+                engine.encode(serde_json::to_vec(&header).unwrap())
+            )
+        };
+        let hmac = |message: String, algorithm: Algorithm, key: &EncodingKey| {
+            let signature = jsonwebtoken::crypto::sign(message.as_bytes(), key, algorithm).unwrap();
+            format!("{message}.{signature}")
+        };
+        match tampering {
+            Tampering::AlgNone => format!("{}.", unsigned("none", None)),
+            Tampering::InvalidSignature => hmac(
+                unsigned("HS512", None),
+                Algorithm::HS512,
+                &EncodingKey::from_secret(FORGED_SECRET),
+            ),
+            Tampering::KeyConfusion => match &self.0.rsa {
+                Some(rsa) => hmac(
+                    unsigned("HS256", Some(RSA_KEY_ID)),
+                    Algorithm::HS256,
+                    &EncodingKey::from_secret(rsa.public_pem.as_bytes()),
+                ),
+                None => hmac(
+                    unsigned("RS256", Some(RSA_KEY_ID)),
+                    Algorithm::HS256,
+                    &self.0.encoding_key,
+                ),
+            },
+            _ => hmac(
+                unsigned("HS512", None),
+                Algorithm::HS512,
+                &self.0.encoding_key,
+            ),
+        }
+    }
+
+    /// Key signing the responses of a client, the symmetric ones using its secret.
+    fn encoding_key(
+        &self,
+        algorithm: jsonwebtoken::Algorithm,
+        client_secret: &str,
+    ) -> Option<jsonwebtoken::EncodingKey> {
+        use jsonwebtoken::Algorithm;
+
+        match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => Some(
+                jsonwebtoken::EncodingKey::from_secret(client_secret.as_bytes()),
+            ),
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => self.0.rsa.as_ref().map(|key| key.encoding_key.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether a key is configured to sign the authorization responses with the algorithm.
+    pub fn can_sign_response(&self, algorithm: jsonwebtoken::Algorithm) -> bool {
+        self.encoding_key(algorithm, "").is_some()
+    }
+
+    fn header(algorithm: jsonwebtoken::Algorithm) -> jsonwebtoken::Header {
+        let mut header = jsonwebtoken::Header::new(algorithm);
+        if !matches!(
+            algorithm,
+            jsonwebtoken::Algorithm::HS256
+                | jsonwebtoken::Algorithm::HS384
+                | jsonwebtoken::Algorithm::HS512
+        ) {
+            header.kid = Some(String::from(RSA_KEY_ID));
+        }
+        header
+    }
+
+    /// Wraps an authorization response in a signed JWT, as defined by JARM.
+    pub fn encode_response(
+        &self,
+        algorithm: jsonwebtoken::Algorithm,
+        client_secret: &str,
+        issuer: &str,
+        audience: &str,
+        mut payload: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let key = self.encoding_key(algorithm, client_secret).ok_or_else(|| {
+            jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidAlgorithm)
+        })?;
+        let expiration = self
+            .0
+            .clock
+            .now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            + RESPONSE_DURATION;
+
+        payload.retain(|_, value| !value.is_null());
+        payload.insert("iss".into(), issuer.into());
+        payload.insert("aud".into(), audience.into());
+        payload.insert("exp".into(), expiration.as_secs().into());
+
+        jsonwebtoken::encode(&Self::header(algorithm), &payload, &key)
+    }
+
+    /// Decrypts a JWE in compact serialization that was encrypted with the RSA public key,
+    /// using `RSA-OAEP` or `RSA-OAEP-256` and `A128GCM` or `A256GCM`.
+    pub fn decrypt(&self, token: &str) -> Result<Vec<u8>, String> {
+        use aes_gcm::aead::{Aead, KeyInit, Payload};
+
+        let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let [header, key, iv, ciphertext, tag]: [&str; 5] = token
+            .split('.')
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| String::from("the token is not a JWE"))?;
+        let decode = |value: &str| {
+            engine
+                .decode(value)
+                .map_err(|err| format!("invalid base64 encoding: {err}"))
+        };
+        let parsed_header: serde_json::Value = serde_json::from_slice(&decode(header)?)
+            .map_err(|err| format!("invalid JWE header: {err}"))?;
+
+        let rsa = self
+            .0
+            .rsa
+            .as_ref()
+            .ok_or_else(|| String::from("no RSA key configured to decrypt the token"))?;
+        let content_key = match parsed_header["alg"].as_str() {
+            Some("RSA-OAEP") => rsa
+                .private_key
+                .decrypt(rsa::Oaep::new::<sha1::Sha1>(), &decode(key)?),
+            Some("RSA-OAEP-256") => rsa
+                .private_key
+                .decrypt(rsa::Oaep::new::<sha2::Sha256>(), &decode(key)?),
+            other => return Err(format!("unsupported key management algorithm {other:?}")),
+        }
+        .map_err(|err| format!("unable to decrypt the content encryption key: {err}"))?;
+
+        let mut message = decode(ciphertext)?;
+        message.extend(decode(tag)?);
+        let iv = decode(iv)?;
+        if iv.len() != 12 {
+            return Err(String::from("invalid initialization vector"));
+        }
+        let payload = Payload {
+            msg: &message,
+            aad: header.as_bytes(),
+        };
+        let nonce = aes_gcm::Nonce::from_slice(&iv);
+        match parsed_header["enc"].as_str() {
+            Some("A128GCM") => aes_gcm::Aes128Gcm::new_from_slice(&content_key)
+                .map_err(|err| err.to_string())?
+                .decrypt(nonce, payload),
+            Some("A256GCM") => aes_gcm::Aes256Gcm::new_from_slice(&content_key)
+                .map_err(|err| err.to_string())?
+                .decrypt(nonce, payload),
+            other => {
+                return Err(format!(
+                    "unsupported content encryption algorithm {other:?}"
+                ))
+            }
+        }
+        .map_err(|_| String::from("unable to decrypt the token"))
+    }
+
+    /// Public keys that can be used to verify the signatures or encrypt the request objects,
+    /// as a JSON Web Key Set.
+    pub fn jwks(&self) -> serde_json::Value {
+        let keys = self
+            .0
+            .rsa
+            .iter()
+            .map(|key| {
+                serde_json::json!({
+                    "kty": "RSA",
+                    "kid": RSA_KEY_ID,
+                    "n": key.modulus,
+                    "e": key.exponent,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({ "keys": keys })
+    }
+
+    /// Decodes an access token issued by this server, the ID tokens being refused.
+    pub fn decode(&self, token: &str) -> Option<JsonWebTokenClaim> {
         jsonwebtoken::decode::<JsonWebTokenClaim>(token, &self.0.decoding_key, &self.0.validation)
             .map_err(|err| {
                 tracing::error!("unable to decode jwt token: {err:?}");
                 err
             })
             .ok()
-            .map(|payload| payload.claims.sub)
+            .filter(|payload| {
+                let access_token = payload
+                    .header
+                    .typ
+                    .as_deref()
+                    .is_some_and(|typ| typ.eq_ignore_ascii_case(ACCESS_TOKEN_TYPE));
+                if !access_token {
+                    tracing::error!("unable to decode jwt token: not an access token");
+                }
+                access_token
+            })
+            .map(|payload| payload.claims)
+            .filter(|claims| {
+                let expired =
+                    claims.exp as u64 + self.0.validation.leeway < self.0.clock.timestamp();
+                if expired {
+                    tracing::error!("unable to decode jwt token: expired");
+                }
+                !expired
+            })
     }
 }
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::service::jsonwebtoken::Tampering;
+
+/// Tampers the tokens issued to a client, for a user, or both. Without any of them, every
+/// issued token is tampered.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub(crate) struct MaliciousRule {
+    pub client_id: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub tampering: Tampering,
+}
+
+/// Rules turning the server into a malicious identity provider.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Malicious(Arc<[MaliciousRule]>);
+
+impl From<Vec<MaliciousRule>> for Malicious {
+    fn from(value: Vec<MaliciousRule>) -> Self {
+        Self(value.into())
+    }
+}
+
+impl Malicious {
+    /// Tampering of the first rule matching the client and the user.
+    pub fn tampering(&self, client_id: &str, user_id: Uuid) -> Option<Tampering> {
+        self.0
+            .iter()
+            .find(|rule| {
+                rule.client_id
+                    .as_deref()
+                    .is_none_or(|expected| expected == client_id)
+                    && rule.user_id.is_none_or(|expected| expected == user_id)
+            })
+            .map(|rule| rule.tampering)
+    }
+}
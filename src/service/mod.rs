@@ -1,15 +1,54 @@
 use std::path::PathBuf;
 
+pub(crate) mod admin;
 pub(crate) mod baseurl;
 pub(crate) mod cache;
+pub(crate) mod chaos;
+pub(crate) mod clock;
+pub(crate) mod consent;
 pub(crate) mod database;
+pub(crate) mod dpop;
+pub(crate) mod fault;
+pub(crate) mod journal;
 pub(crate) mod jsonwebtoken;
+pub(crate) mod malicious;
 pub(crate) mod oauth;
+pub(crate) mod opaquetoken;
+pub(crate) mod random;
+pub(crate) mod registration;
+pub(crate) mod tls;
 
 #[derive(serde::Deserialize)]
 pub(crate) struct Config {
     pub oauth: oauth::Config,
+    /// Enables the admin API, protected by a bearer token.
+    pub admin: Option<admin::Config>,
+    /// Size and lifetime of the caches of the pending flows.
+    #[serde(default)]
+    pub cache: cache::Config,
     pub jsonwebtoken: jsonwebtoken::Config,
+    #[serde(default)]
+    pub dpop: dpop::Config,
+    /// Seeds the generated values and freezes the clock, for reproducible responses.
+    pub deterministic: Option<random::Config>,
+    /// Chaos scenarios, that can be switched at runtime.
+    #[serde(default)]
+    pub chaos: Vec<chaos::ChaosScenario>,
+    /// Fault injection rules, applied from the start.
+    #[serde(default)]
+    pub faults: Vec<fault::FaultRule>,
+    #[serde(default)]
+    pub journal: journal::Config,
+    /// Tokens deliberately issued with a flaw.
+    #[serde(default)]
+    pub malicious: Vec<malicious::MaliciousRule>,
+    /// Enables the dynamic client registration.
+    pub registration: Option<registration::Config>,
+    /// Serve over TLS, requesting a certificate to the clients.
+    pub tls: Option<tls::Config>,
+    /// Long-lived tokens, accepted alongside the issued JWTs.
+    #[serde(default)]
+    pub tokens: Vec<opaquetoken::Config>,
     pub users: Vec<crate::entity::user::User>,
 }
 
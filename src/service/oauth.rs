@@ -1,12 +1,171 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
-use crate::entity::authorization::{AuthorizationError, AuthorizationRequest};
+use crate::entity::authorization::{
+    deserialize_one_or_many, AuthorizationError, AuthorizationRequest, ResponseMode, ResponseType,
+};
+use crate::entity::backchannel::BackchannelTokenDeliveryMode;
+use crate::entity::registration::ClientMetadata;
+use crate::service::jsonwebtoken::JsonWebToken;
+use crate::service::tls::ClientCertificate;
+
+/// Methods the client can use to authenticate on the token endpoint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TokenEndpointAuthMethod {
+    #[default]
+    ClientSecretBasic,
+    /// Certificate issued by a trusted authority, as defined by RFC 8705.
+    TlsClientAuth,
+    /// Self-signed certificate registered in the client key set, as defined by RFC 8705.
+    SelfSignedTlsClientAuth,
+}
+
+/// Token exchanges the client is allowed to perform, as defined by RFC 8693.
+#[derive(Default, serde::Deserialize)]
+pub(crate) struct TokenExchangePolicy {
+    /// Exchange a subject token for a token issued to the same subject.
+    #[serde(default)]
+    pub impersonation: bool,
+    /// Exchange a subject token and an actor token for a token carrying an `act` claim.
+    #[serde(default)]
+    pub delegation: bool,
+}
+
+/// Issuer whose JWT assertions can be exchanged for access tokens, as defined by RFC 7523.
+#[derive(serde::Deserialize)]
+pub(crate) struct TrustedIssuer {
+    /// Expected `iss` claim of the assertions.
+    pub issuer: String,
+    /// Public keys used to verify the assertions.
+    pub jwks: jsonwebtoken::jwk::JwkSet,
+}
+
+/// Claims of a verified JWT bearer assertion.
+#[derive(Debug)]
+pub(crate) struct Assertion {
+    pub sub: String,
+}
 
 #[derive(serde::Deserialize)]
 pub(crate) struct Config {
     pub client_id: String,
     pub client_secret: String,
-    pub redirect_uri: String,
+    /// Callback URLs registered for the client, one or many.
+    #[serde(deserialize_with = "deserialize_one_or_many")]
+    pub redirect_uri: Vec<String>,
+    /// Name displayed to the user on the consent page.
+    pub client_name: Option<String>,
+    /// Ask the user to approve the requested scopes before issuing a code.
+    #[serde(default)]
+    pub consent: bool,
+    /// Response types the client is allowed to request, `code` only by default.
+    #[serde(default = "Config::default_response_types")]
+    pub response_types: Vec<ResponseType>,
+    /// Response modes the client is allowed to request, all of them by default.
+    #[serde(default = "Config::default_response_modes")]
+    pub response_modes: Vec<ResponseMode>,
+    /// Resources the client can request access tokens for, as defined by RFC 8707.
+    #[serde(default)]
+    pub resources: Vec<String>,
+    /// Token exchanges the client is allowed to perform, none by default.
+    #[serde(default)]
+    pub token_exchange: TokenExchangePolicy,
+    /// Issuers of the JWT assertions the client can exchange for access tokens.
+    #[serde(default)]
+    pub trusted_issuers: Vec<TrustedIssuer>,
+    /// Types of authorization details the client is allowed to request.
+    #[serde(default)]
+    pub authorization_details_types: Vec<String>,
+    /// Delivery mode of the backchannel authentications (CIBA), disabled when not set.
+    pub backchannel_token_delivery_mode: Option<BackchannelTokenDeliveryMode>,
+    /// Endpoint called back with the ping and push delivery modes.
+    pub backchannel_client_notification_endpoint: Option<String>,
+    /// Only accept authorization requests pushed to the PAR endpoint.
+    #[serde(default)]
+    pub require_pushed_authorization_requests: bool,
+    /// Require the access tokens to be bound to a DPoP proof key.
+    #[serde(default)]
+    pub dpop_bound_access_tokens: bool,
+    /// Method used by the client to authenticate on the token endpoint.
+    #[serde(default)]
+    pub token_endpoint_auth_method: TokenEndpointAuthMethod,
+    /// Subject of the client certificate expected with `tls_client_auth`.
+    pub tls_client_auth_subject_dn: Option<String>,
+    /// Bind the access tokens to the client certificate used on the token endpoint.
+    #[serde(default)]
+    pub tls_client_certificate_bound_access_tokens: bool,
+    /// Public keys used to verify the request objects signed with asymmetric algorithms,
+    /// and the self-signed certificates of the client in their `x5c` member.
+    pub jwks: Option<jsonwebtoken::jwk::JwkSet>,
+    /// URLs the request objects can be fetched from, none by default.
+    #[serde(default)]
+    pub request_uris: Vec<String>,
+    /// Algorithm used to sign the JWT secured authorization responses, HS512 with the client
+    /// secret by default, the RS* and PS* ones requiring the RSA private key.
+    #[serde(default = "Config::default_authorization_signed_response_alg")]
+    pub authorization_signed_response_alg: jsonwebtoken::Algorithm,
+    /// Token managing the registration of a dynamically registered client.
+    #[serde(skip)]
+    pub registration_access_token: Option<String>,
+}
+
+impl Config {
+    fn default_response_types() -> Vec<ResponseType> {
+        vec![ResponseType {
+            code: true,
+            ..Default::default()
+        }]
+    }
+
+    fn default_response_modes() -> Vec<ResponseMode> {
+        ResponseMode::ALL.to_vec()
+    }
+
+    fn default_authorization_signed_response_alg() -> jsonwebtoken::Algorithm {
+        jsonwebtoken::Algorithm::HS512
+    }
+
+    /// Builds the configuration of a dynamically registered client from its metadata.
+    pub fn from_metadata(
+        client_id: String,
+        client_secret: String,
+        registration_access_token: String,
+        metadata: ClientMetadata,
+    ) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            redirect_uri: metadata.redirect_uris,
+            client_name: metadata.client_name,
+            consent: false,
+            response_types: metadata
+                .response_types
+                .unwrap_or_else(Self::default_response_types),
+            response_modes: Self::default_response_modes(),
+            resources: Vec::new(),
+            token_exchange: TokenExchangePolicy::default(),
+            trusted_issuers: Vec::new(),
+            authorization_details_types: Vec::new(),
+            backchannel_token_delivery_mode: None,
+            backchannel_client_notification_endpoint: None,
+            require_pushed_authorization_requests: false,
+            dpop_bound_access_tokens: metadata.dpop_bound_access_tokens,
+            token_endpoint_auth_method: metadata.token_endpoint_auth_method,
+            tls_client_auth_subject_dn: metadata.tls_client_auth_subject_dn,
+            tls_client_certificate_bound_access_tokens: metadata
+                .tls_client_certificate_bound_access_tokens,
+            jwks: metadata.jwks,
+            request_uris: metadata.request_uris,
+            authorization_signed_response_alg: metadata
+                .authorization_signed_response_alg
+                .unwrap_or_else(Self::default_authorization_signed_response_alg),
+            registration_access_token: Some(registration_access_token),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -15,35 +174,379 @@ impl Default for Config {
         Self {
             client_id: String::from("client-id"),
             client_secret: String::from("client-secret"),
-            redirect_uri: String::from("http://app/api/redirect"),
+            redirect_uri: vec![String::from("http://app/api/redirect")],
+            client_name: None,
+            consent: false,
+            response_types: Self::default_response_types(),
+            response_modes: Self::default_response_modes(),
+            resources: Vec::new(),
+            token_exchange: TokenExchangePolicy::default(),
+            trusted_issuers: Vec::new(),
+            authorization_details_types: Vec::new(),
+            backchannel_token_delivery_mode: None,
+            backchannel_client_notification_endpoint: None,
+            require_pushed_authorization_requests: false,
+            dpop_bound_access_tokens: false,
+            token_endpoint_auth_method: TokenEndpointAuthMethod::default(),
+            tls_client_auth_subject_dn: None,
+            tls_client_certificate_bound_access_tokens: false,
+            jwks: None,
+            request_uris: Vec::new(),
+            authorization_signed_response_alg: Self::default_authorization_signed_response_alg(),
+            registration_access_token: None,
         }
     }
 }
 
+fn invalid_request_object(description: impl Into<Cow<'static, str>>) -> AuthorizationError {
+    AuthorizationError {
+        error: "invalid_request_object".into(),
+        error_description: description.into(),
+        state: None,
+    }
+}
+
+fn invalid_grant(description: impl Into<Cow<'static, str>>) -> AuthorizationError {
+    AuthorizationError {
+        error: "invalid_grant".into(),
+        error_description: description.into(),
+        state: None,
+    }
+}
+
+/// Finds the key matching the `kid` of the header, or the only key of the set.
+fn find_key<'a>(
+    keys: &'a [jsonwebtoken::jwk::Jwk],
+    header: &jsonwebtoken::Header,
+) -> Option<&'a jsonwebtoken::jwk::Jwk> {
+    match header.kid.as_deref() {
+        Some(kid) => keys
+            .iter()
+            .find(|key| key.common.key_id.as_deref() == Some(kid)),
+        None if keys.len() == 1 => keys.first(),
+        None => None,
+    }
+}
+
+fn invalid_client(description: &'static str) -> AuthorizationError {
+    AuthorizationError {
+        error: "invalid_client".into(),
+        error_description: description.into(),
+        state: None,
+    }
+}
+
+fn invalid_client_id(state: Option<String>) -> AuthorizationError {
+    AuthorizationError {
+        error: "invalid_client_id".into(),
+        error_description: "Unable to find an application with the provided client_id.".into(),
+        state,
+    }
+}
+
+/// Client registered in the configuration or through the admin API.
 #[derive(Clone)]
-pub(crate) struct Oauth(Arc<Config>);
+pub(crate) struct Client(Arc<Config>);
 
-impl Oauth {
-    pub fn check_basic_token(
+impl Client {
+    pub fn client_id(&self) -> &str {
+        &self.0.client_id
+    }
+
+    pub fn redirect_uris(&self) -> &[String] {
+        &self.0.redirect_uri
+    }
+
+    pub fn client_secret(&self) -> &str {
+        &self.0.client_secret
+    }
+
+    /// Algorithm used to sign the JWT secured authorization responses, HS* using the secret.
+    pub fn authorization_signed_response_alg(&self) -> jsonwebtoken::Algorithm {
+        self.0.authorization_signed_response_alg
+    }
+
+    /// Whether the token manages the registration of this client.
+    pub fn is_registration_access_token(&self, token: &str) -> bool {
+        self.0.registration_access_token.as_deref() == Some(token)
+    }
+
+    pub fn registration_access_token(&self) -> Option<&str> {
+        self.0.registration_access_token.as_deref()
+    }
+
+    /// Metadata of the client, as returned by the dynamic client registration.
+    pub fn metadata(&self) -> ClientMetadata {
+        ClientMetadata {
+            redirect_uris: self.0.redirect_uri.clone(),
+            client_name: self.0.client_name.clone(),
+            token_endpoint_auth_method: self.0.token_endpoint_auth_method,
+            response_types: Some(self.0.response_types.clone()),
+            jwks: self.0.jwks.clone(),
+            tls_client_auth_subject_dn: self.0.tls_client_auth_subject_dn.clone(),
+            tls_client_certificate_bound_access_tokens: self
+                .0
+                .tls_client_certificate_bound_access_tokens,
+            dpop_bound_access_tokens: self.0.dpop_bound_access_tokens,
+            request_uris: self.0.request_uris.clone(),
+            authorization_signed_response_alg: Some(self.0.authorization_signed_response_alg),
+        }
+    }
+
+    /// Whether the request objects can be fetched from this URL, ignoring its fragment.
+    pub fn allows_request_uri(&self, request_uri: &str) -> bool {
+        let request_uri = request_uri.split('#').next().unwrap_or_default();
+        self.0
+            .request_uris
+            .iter()
+            .any(|allowed| allowed.split('#').next() == Some(request_uri))
+    }
+
+    pub fn client_name(&self) -> &str {
+        self.0.client_name.as_deref().unwrap_or(&self.0.client_id)
+    }
+
+    pub fn requires_consent(&self) -> bool {
+        self.0.consent
+    }
+
+    pub fn check_pushed(
         &self,
-        client_id: &str,
-        client_secret: &str,
+        req: &AuthorizationRequest,
+        pushed: bool,
     ) -> Result<(), AuthorizationError> {
-        if !self.0.client_id.eq(client_id) {
-            Err(AuthorizationError {
-                error: "invalid_client_id".into(),
-                error_description: "Unable to find an application with the provided client_id."
-                    .into(),
-                state: None,
+        if self.0.require_pushed_authorization_requests && !pushed {
+            return Err(AuthorizationError {
+                error: "invalid_request".into(),
+                error_description:
+                    "This application requires authorization requests to be pushed first.".into(),
+                state: Some(req.state.clone()),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn request_object_key(
+        &self,
+        header: &jsonwebtoken::Header,
+    ) -> Result<jsonwebtoken::DecodingKey, AuthorizationError> {
+        use jsonwebtoken::Algorithm;
+
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Ok(jsonwebtoken::DecodingKey::from_secret(
+                self.0.client_secret.as_bytes(),
+            ));
+        }
+        let keys = self
+            .0
+            .jwks
+            .as_ref()
+            .map(|set| set.keys.as_slice())
+            .unwrap_or_default();
+        let jwk = find_key(keys, header).ok_or_else(|| {
+            invalid_request_object("Unable to find the client key used to sign the request object.")
+        })?;
+        jsonwebtoken::DecodingKey::from_jwk(jwk)
+            .map_err(|err| invalid_request_object(format!("Invalid client key: {err}")))
+    }
+
+    /// Verifies a request object (JAR) signed, and optionally encrypted, by the client
+    /// and extracts the authorization request it contains.
+    fn decode_request_object(
+        &self,
+        jwt: &JsonWebToken,
+        issuer: &str,
+        token: &str,
+    ) -> Result<AuthorizationRequest, AuthorizationError> {
+        let client_id = self.client_id();
+        let decrypted;
+        let token = if token.split('.').count() == 5 {
+            decrypted = jwt
+                .decrypt(token)
+                .ok()
+                .and_then(|value| String::from_utf8(value).ok())
+                .ok_or_else(|| invalid_request_object("Unable to decrypt the request object."))?;
+            decrypted.as_str()
+        } else {
+            token
+        };
+
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|err| invalid_request_object(format!("Invalid request object: {err}")))?;
+        let key = self.request_object_key(&header)?;
+        let mut validation = jsonwebtoken::Validation::new(header.alg);
+        validation.required_spec_claims.clear();
+        validation.validate_aud = false;
+        let claims = jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation)
+            .map_err(|err| invalid_request_object(format!("Invalid request object: {err}")))?
+            .claims;
+
+        if claims["client_id"].as_str() != Some(client_id) {
+            return Err(invalid_request_object(
+                "The client_id of the request object doesn't match the one of the request.",
+            ));
+        }
+        if !claims["iss"].is_null() && claims["iss"].as_str() != Some(client_id) {
+            return Err(invalid_request_object(
+                "The request object must be issued by the client.",
+            ));
+        }
+        let audience_matches = match &claims["aud"] {
+            serde_json::Value::Null => true,
+            serde_json::Value::String(aud) => aud == issuer,
+            serde_json::Value::Array(items) => items.iter().any(|aud| aud == issuer),
+            _ => false,
+        };
+        if !audience_matches {
+            return Err(invalid_request_object(
+                "The audience of the request object must be the authorization server.",
+            ));
+        }
+
+        serde_json::from_value(claims)
+            .map_err(|err| invalid_request_object(format!("Invalid authorization request: {err}")))
+    }
+
+    /// Verifies a JWT bearer assertion signed by one of the trusted issuers,
+    /// and intended for the authorization server.
+    pub fn decode_assertion(
+        &self,
+        audience: &[String],
+        token: &str,
+    ) -> Result<Assertion, AuthorizationError> {
+        use jsonwebtoken::errors::ErrorKind;
+
+        let header = jsonwebtoken::decode_header(token)
+            .map_err(|_| invalid_grant("The assertion is not a valid JWT."))?;
+        // the issuer is read before verifying the signature, to find its keys
+        let mut unverified = jsonwebtoken::Validation::new(header.alg);
+        unverified.insecure_disable_signature_validation();
+        unverified.required_spec_claims.clear();
+        unverified.validate_aud = false;
+        unverified.validate_exp = false;
+        let claims = jsonwebtoken::decode::<serde_json::Value>(
+            token,
+            &jsonwebtoken::DecodingKey::from_secret(&[]),
+            &unverified,
+        )
+        .map_err(|_| invalid_grant("The assertion is not a valid JWT."))?
+        .claims;
+        let issuer = claims["iss"]
+            .as_str()
+            .ok_or_else(|| invalid_grant("The assertion must have an issuer."))?;
+        let trusted = self
+            .0
+            .trusted_issuers
+            .iter()
+            .find(|item| item.issuer == issuer)
+            .ok_or_else(|| invalid_grant(format!("The issuer {issuer:?} is not trusted.")))?;
+        let key = find_key(&trusted.jwks.keys, &header)
+            .and_then(|jwk| jsonwebtoken::DecodingKey::from_jwk(jwk).ok())
+            .ok_or_else(|| {
+                invalid_grant("Unable to find the issuer key used to sign the assertion.")
+            })?;
+
+        let mut validation = jsonwebtoken::Validation::new(header.alg);
+        validation.set_required_spec_claims(&["exp", "iss", "sub", "aud"]);
+        validation.set_issuer(&[issuer]);
+        validation.set_audience(audience);
+        validation.validate_nbf = true;
+        // the claims are only read once validated, so that a missing subject is reported as such
+        jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation)
+            .map(|data| Assertion {
+                sub: data.claims["sub"].as_str().unwrap_or_default().to_string(),
             })
-        } else if !self.0.client_secret.eq(client_secret) {
-            Err(AuthorizationError {
-                error: "invalid_client_secret".into(),
-                error_description: "The provided client secret is invalid.".into(),
-                state: None,
+            .map_err(|err| {
+                invalid_grant(match err.kind() {
+                    ErrorKind::InvalidSignature => "The assertion signature is invalid.",
+                    ErrorKind::ExpiredSignature => "The assertion has expired.",
+                    ErrorKind::ImmatureSignature => "The assertion is not valid yet.",
+                    ErrorKind::InvalidAudience => {
+                        "The audience of the assertion must be the authorization server."
+                    }
+                    ErrorKind::MissingRequiredClaim(claim) => {
+                        return invalid_grant(format!(
+                            "The assertion is missing the {claim:?} claim."
+                        ))
+                    }
+                    _ => "The assertion is invalid.",
+                })
             })
-        } else {
-            Ok(())
+    }
+
+    pub fn requires_dpop(&self) -> bool {
+        self.0.dpop_bound_access_tokens
+    }
+
+    pub fn binds_certificate(&self) -> bool {
+        self.0.tls_client_certificate_bound_access_tokens
+    }
+
+    /// Whether the certificate is one of the self-signed certificates registered by the client.
+    fn is_registered_certificate(&self, certificate: &ClientCertificate) -> bool {
+        let engine = base64::engine::general_purpose::STANDARD;
+        self.0
+            .jwks
+            .iter()
+            .flat_map(|set| set.keys.iter())
+            .filter_map(|key| key.common.x509_chain.as_ref()?.first())
+            .any(|encoded| {
+                base64::Engine::decode(&engine, encoded)
+                    .is_ok_and(|der| der.as_slice() == certificate.der())
+            })
+    }
+
+    /// Authenticates the client with the method it registered, using the secret of the HTTP Basic
+    /// credentials or the certificate presented during the TLS handshake.
+    fn authenticate(
+        &self,
+        secret: Option<&str>,
+        certificate: Option<&ClientCertificate>,
+    ) -> Result<(), AuthorizationError> {
+        match self.0.token_endpoint_auth_method {
+            TokenEndpointAuthMethod::ClientSecretBasic => match secret {
+                Some(secret) if secret == self.0.client_secret => Ok(()),
+                Some(_) => Err(AuthorizationError {
+                    error: "invalid_client_secret".into(),
+                    error_description: "The provided client secret is invalid.".into(),
+                    state: None,
+                }),
+                None => Err(invalid_client(
+                    "The client must authenticate with HTTP Basic.",
+                )),
+            },
+            TokenEndpointAuthMethod::TlsClientAuth => {
+                let certificate = certificate.ok_or_else(|| {
+                    invalid_client("The client must authenticate with a TLS certificate.")
+                })?;
+                if !certificate.is_trusted() {
+                    return Err(invalid_client(
+                        "The client certificate isn't issued by a trusted authority.",
+                    ));
+                }
+                if certificate.subject().as_deref() != self.0.tls_client_auth_subject_dn.as_deref()
+                {
+                    return Err(invalid_client(
+                        "The client certificate subject doesn't match the registered one.",
+                    ));
+                }
+                Ok(())
+            }
+            TokenEndpointAuthMethod::SelfSignedTlsClientAuth => {
+                let certificate = certificate.ok_or_else(|| {
+                    invalid_client("The client must authenticate with a TLS certificate.")
+                })?;
+                if !self.is_registered_certificate(certificate) {
+                    return Err(invalid_client(
+                        "The client certificate isn't registered for this application.",
+                    ));
+                }
+                Ok(())
+            }
         }
     }
 
@@ -52,7 +555,7 @@ impl Oauth {
         uri: &str,
         state: Option<String>,
     ) -> Result<(), AuthorizationError> {
-        if !self.0.redirect_uri.eq(uri) {
+        if !self.0.redirect_uri.iter().any(|item| item == uri) {
             return Err(AuthorizationError {
                 error: Cow::Borrowed("redirect_uri_mismatch"),
                 error_description: Cow::Borrowed(
@@ -65,21 +568,119 @@ impl Oauth {
         Ok(())
     }
 
+    pub fn check_token_exchange(&self, delegation: bool) -> Result<(), AuthorizationError> {
+        let (allowed, kind) = if delegation {
+            (self.0.token_exchange.delegation, "delegation")
+        } else {
+            (self.0.token_exchange.impersonation, "impersonation")
+        };
+        if !allowed {
+            return Err(AuthorizationError {
+                error: "unauthorized_client".into(),
+                error_description: format!(
+                    "This application is not allowed to perform a token exchange by {kind}."
+                )
+                .into(),
+                state: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Delivery mode of the backchannel authentications, when the client is allowed to start them.
+    pub fn backchannel_token_delivery_mode(
+        &self,
+    ) -> Result<BackchannelTokenDeliveryMode, AuthorizationError> {
+        match self.0.backchannel_token_delivery_mode {
+            Some(mode)
+                if !mode.notifies()
+                    || self.0.backchannel_client_notification_endpoint.is_some() =>
+            {
+                Ok(mode)
+            }
+            _ => Err(AuthorizationError {
+                error: "unauthorized_client".into(),
+                error_description:
+                    "This application is not registered for backchannel authentication.".into(),
+                state: None,
+            }),
+        }
+    }
+
+    pub fn backchannel_client_notification_endpoint(&self) -> Option<&str> {
+        self.0.backchannel_client_notification_endpoint.as_deref()
+    }
+
+    pub fn check_resources(&self, resources: &[String]) -> Result<(), AuthorizationError> {
+        match resources
+            .iter()
+            .find(|resource| !self.0.resources.contains(resource))
+        {
+            Some(resource) => Err(AuthorizationError {
+                error: "invalid_target".into(),
+                error_description: format!(
+                    "The resource {resource:?} is not allowed for this application."
+                )
+                .into(),
+                state: None,
+            }),
+            None => Ok(()),
+        }
+    }
+
     pub fn check(&self, req: &AuthorizationRequest) -> Result<(), AuthorizationError> {
-        if !self.0.client_id.eq(&req.client_id) {
+        self.check_redirect_uri(&req.redirect_uri, Some(req.state.clone()))?;
+        if !self.0.response_types.contains(&req.response_type) {
+            return Err(AuthorizationError {
+                error: "unsupported_response_type".into(),
+                error_description: format!(
+                    "The response type {:?} is not allowed for this application.",
+                    req.response_type.to_string()
+                )
+                .into(),
+                state: Some(req.state.clone()),
+            });
+        }
+        let response_mode = req.response_mode();
+        let requested_mode = req.response_mode.unwrap_or(response_mode);
+        if !self.0.response_modes.contains(&requested_mode)
+            || (response_mode.transport() == ResponseMode::Query
+                && (req.response_type.token || req.response_type.id_token))
+        {
             return Err(AuthorizationError {
-                error: "invalid_client_id".into(),
-                error_description: "Unable to find an application with the provided client_id."
+                error: "unsupported_response_mode".into(),
+                error_description: "The response mode is not allowed for this response type."
                     .into(),
                 state: Some(req.state.clone()),
             });
         }
-        if !self.0.redirect_uri.eq(&req.redirect_uri) {
+        self.check_resources(&req.resource)
+            .map_err(|err| AuthorizationError {
+                state: Some(req.state.clone()),
+                ..err
+            })?;
+        if let Some(kind) = req.authorization_details_types().find(|kind| {
+            !self
+                .0
+                .authorization_details_types
+                .iter()
+                .any(|item| item == kind)
+        }) {
             return Err(AuthorizationError {
-                error: Cow::Borrowed("redirect_uri_mismatch"),
-                error_description: Cow::Borrowed(
-                    "The redirect_uri MUST match the registered callback URL for this application.",
-                ),
+                error: "invalid_authorization_details".into(),
+                error_description: format!(
+                    "The authorization details type {kind:?} is not allowed for this application."
+                )
+                .into(),
+                state: Some(req.state.clone()),
+            });
+        }
+        if req.response_type.id_token && req.nonce.is_none() {
+            return Err(AuthorizationError {
+                error: "invalid_request".into(),
+                error_description: "The nonce parameter is required when requesting an id_token."
+                    .into(),
                 state: Some(req.state.clone()),
             });
         }
@@ -88,8 +689,97 @@ impl Oauth {
     }
 }
 
+/// Clients registered in the configuration, or at runtime through the admin API.
+#[derive(Clone)]
+pub(crate) struct Oauth(Arc<RwLock<HashMap<String, Client>>>);
+
+impl Oauth {
+    pub fn client(&self, client_id: &str) -> Result<Client, AuthorizationError> {
+        self.0
+            .read()
+            .unwrap()
+            .get(client_id)
+            .cloned()
+            .ok_or_else(|| invalid_client_id(None))
+    }
+
+    /// Lists the clients, sorted by id.
+    pub fn list(&self) -> Vec<Client> {
+        let mut clients = self.0.read().unwrap().values().cloned().collect::<Vec<_>>();
+        clients.sort_by(|first, second| first.client_id().cmp(second.client_id()));
+        clients
+    }
+
+    /// Registers or replaces the client, returning the previous one.
+    pub fn register(&self, config: Config) -> Option<Client> {
+        self.0
+            .write()
+            .unwrap()
+            .insert(config.client_id.clone(), Client(Arc::new(config)))
+    }
+
+    pub fn remove(&self, client_id: &str) -> Option<Client> {
+        self.0.write().unwrap().remove(client_id)
+    }
+
+    /// Verifies a request object (JAR) with the keys of the client it's issued by.
+    pub fn decode_request_object(
+        &self,
+        jwt: &JsonWebToken,
+        issuer: &str,
+        client_id: &str,
+        token: &str,
+    ) -> Result<AuthorizationRequest, AuthorizationError> {
+        self.client(client_id)?
+            .decode_request_object(jwt, issuer, token)
+    }
+
+    /// Authenticates the client with the method it registered, using the HTTP Basic
+    /// credentials or the certificate presented during the TLS handshake.
+    pub fn authenticate_client(
+        &self,
+        client_id: Option<&str>,
+        basic: Option<(&str, &str)>,
+        certificate: Option<&ClientCertificate>,
+    ) -> Result<Client, AuthorizationError> {
+        let presented = basic.map(|(username, _)| username).or(client_id);
+        let consistent = match (basic, client_id) {
+            (Some((username, _)), Some(client_id)) => username == client_id,
+            _ => true,
+        };
+        let client = presented
+            .filter(|_| consistent)
+            .and_then(|client_id| self.client(client_id).ok())
+            .ok_or_else(|| invalid_client_id(None))?;
+        client.authenticate(basic.map(|(_, secret)| secret), certificate)?;
+        Ok(client)
+    }
+
+    /// Finds the client the authorization request is issued for, as long as it registered
+    /// the redirect_uri, that the other errors can then be sent to.
+    pub fn redirect_client(
+        &self,
+        req: &AuthorizationRequest,
+    ) -> Result<Client, AuthorizationError> {
+        let client = self
+            .client(&req.client_id)
+            .map_err(|_| invalid_client_id(Some(req.state.clone())))?;
+        client.check_redirect_uri(&req.redirect_uri, Some(req.state.clone()))?;
+        Ok(client)
+    }
+
+    /// Checks the authorization request against the client it's issued for.
+    pub fn check(&self, req: &AuthorizationRequest) -> Result<Client, AuthorizationError> {
+        let client = self.redirect_client(req)?;
+        client.check(req)?;
+        Ok(client)
+    }
+}
+
 impl From<Config> for Oauth {
     fn from(value: Config) -> Self {
-        Self(Arc::new(value))
+        let oauth = Self(Arc::default());
+        oauth.register(value);
+        oauth
     }
 }
@@ -0,0 +1,145 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use uuid::Uuid;
+
+use super::clock::Clock;
+use super::random::Random;
+
+/// Long-lived token declared in the configuration.
+#[derive(serde::Deserialize)]
+pub(crate) struct Config {
+    pub token: String,
+    pub user_id: Uuid,
+    pub scope: Option<String>,
+    /// Expiration time of the token, as UTC timestamp.
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OpaqueTokenKind {
+    /// Declared in the configuration.
+    Static,
+    /// Created with the admin API.
+    Personal,
+}
+
+/// Token that isn't a JWT, and is only known by this server.
+#[derive(Clone, Debug)]
+pub(crate) struct OpaqueToken {
+    pub id: Uuid,
+    pub kind: OpaqueTokenKind,
+    pub name: Option<String>,
+    pub user_id: Uuid,
+    pub scope: Option<String>,
+    pub expires_at: Option<SystemTime>,
+}
+
+impl OpaqueToken {
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Static and personal access tokens, indexed by their value.
+#[derive(Clone, Debug)]
+pub(crate) struct OpaqueTokens {
+    tokens: Arc<RwLock<HashMap<String, OpaqueToken>>>,
+    clock: Clock,
+    random: Random,
+}
+
+impl OpaqueTokens {
+    pub fn new(configs: Vec<Config>, clock: Clock, random: Random) -> Self {
+        let tokens = configs
+            .into_iter()
+            .map(|config| {
+                let token = OpaqueToken {
+                    id: random.uuid(),
+                    kind: OpaqueTokenKind::Static,
+                    name: None,
+                    user_id: config.user_id,
+                    scope: config.scope,
+                    expires_at: config
+                        .expires_at
+                        .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+                };
+                (config.token, token)
+            })
+            .collect();
+        Self {
+            tokens: Arc::new(RwLock::new(tokens)),
+            clock,
+            random,
+        }
+    }
+
+    /// Finds the token, unless it expired.
+    pub fn find(&self, token: &str) -> Option<OpaqueToken> {
+        let now = self.clock.now();
+        self.tokens
+            .read()
+            .unwrap()
+            .get(token)
+            .filter(|token| !token.is_expired(now))
+            .cloned()
+    }
+
+    /// Lists the personal access tokens, without their value.
+    pub fn personal(&self) -> Vec<OpaqueToken> {
+        let mut tokens = self
+            .tokens
+            .read()
+            .unwrap()
+            .values()
+            .filter(|token| token.kind == OpaqueTokenKind::Personal)
+            .cloned()
+            .collect::<Vec<_>>();
+        tokens.sort_by(|left, right| left.name.cmp(&right.name));
+        tokens
+    }
+
+    /// Creates a personal access token, returning its value,
+    /// or nothing when its expiration can't be represented.
+    pub fn create(
+        &self,
+        name: String,
+        user_id: Uuid,
+        scope: Option<String>,
+        expires_in: Option<Duration>,
+    ) -> Option<(String, OpaqueToken)> {
+        let expires_at = match expires_in {
+            Some(duration) => Some(self.clock.now().checked_add(duration)?),
+            None => None,
+        };
+        // prefixed like the GitHub ones, so they are easy to recognise
+        let value = format!("pat_{}", self.random.uuid().simple());
+        let token = OpaqueToken {
+            id: self.random.uuid(),
+            kind: OpaqueTokenKind::Personal,
+            name: Some(name),
+            user_id,
+            scope,
+            expires_at,
+        };
+        self.tokens
+            .write()
+            .unwrap()
+            .insert(value.clone(), token.clone());
+        Some((value, token))
+    }
+
+    /// Revokes the personal access token with this id.
+    pub fn revoke(&self, id: Uuid) -> Option<OpaqueToken> {
+        let mut tokens = self.tokens.write().unwrap();
+        let value = tokens
+            .iter()
+            .find(|(_, token)| token.id == id && token.kind == OpaqueTokenKind::Personal)
+            .map(|(value, _)| value.clone())?;
+        tokens.remove(&value)
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use uuid::Uuid;
+
+use super::clock::{Clock, MAX_TIMESTAMP};
+
+/// Enables the deterministic mode, so that the same flows produce the same responses.
+#[derive(serde::Deserialize)]
+pub(crate) struct Config {
+    /// Seed of the generated codes, tokens, identifiers and secrets.
+    /// The signing keys aren't generated but read from the configuration, so they don't depend on it,
+    /// and the PS256, PS384 and PS512 signatures keep their random salt.
+    pub seed: u64,
+    /// Time the clock is frozen at, as UTC timestamp, up to the end of year 9999.
+    #[serde(default = "Config::default_now")]
+    pub now: u64,
+}
+
+impl Config {
+    fn default_now() -> u64 {
+        // 2024-01-01T00:00:00Z
+        1_704_067_200
+    }
+
+    pub fn clock(&self) -> Clock {
+        Clock::frozen(SystemTime::UNIX_EPOCH + Duration::from_secs(self.now.min(MAX_TIMESTAMP)))
+    }
+}
+
+/// Source of the random values, seeded in the deterministic mode.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Random(Option<Arc<Mutex<u64>>>);
+
+impl From<Option<&Config>> for Random {
+    fn from(value: Option<&Config>) -> Self {
+        Self(value.map(|config| Arc::new(Mutex::new(config.seed))))
+    }
+}
+
+impl Random {
+    /// Next value of the SplitMix64 sequence.
+    fn next(state: &mut u64) -> u64 {
+        *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = *state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+
+    /// Random number between 0 and 1.
+    pub fn float(&self) -> f64 {
+        let value = match &self.0 {
+            Some(state) => Self::next(&mut state.lock().unwrap()),
+            None => Uuid::new_v4().as_u64_pair().0,
+        };
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        let Some(state) = &self.0 else {
+            return Uuid::new_v4();
+        };
+        let mut state = state.lock().unwrap();
+        let high = Self::next(&mut state) as u128;
+        let low = Self::next(&mut state) as u128;
+        uuid::Builder::from_random_bytes(((high << 64) | low).to_be_bytes()).into_uuid()
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+#[derive(Default, serde::Deserialize)]
+pub(crate) struct Config {
+    /// Token required to register a client, the registration is open when not set.
+    pub initial_access_token: Option<String>,
+}
+
+/// Guards the dynamic client registration, that is disabled when not configured.
+#[derive(Clone, Default)]
+pub(crate) struct Registration(Option<Arc<Config>>);
+
+impl Registration {
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether the token, if any, allows to register a client.
+    pub fn authorize(&self, token: Option<&str>) -> bool {
+        match self.0.as_deref() {
+            Some(Config {
+                initial_access_token: Some(expected),
+            }) => token == Some(expected.as_str()),
+            Some(_) => true,
+            None => false,
+        }
+    }
+}
+
+impl From<Option<Config>> for Registration {
+    fn from(value: Option<Config>) -> Self {
+        Self(value.map(Arc::new))
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use base64::Engine;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::service::TowerToHyperService;
+use sha2::Digest;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::{
+    self,
+    client::danger::HandshakeSignatureValid,
+    crypto::CryptoProvider,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, UnixTime},
+    server::danger::{ClientCertVerified, ClientCertVerifier},
+    DigitallySignedStruct, DistinguishedName, SignatureScheme,
+};
+use tokio_rustls::TlsAcceptor;
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Config {
+    /// PEM encoded certificate chain presented by the server.
+    pub certificate: String,
+    /// PEM encoded private key of the server certificate.
+    pub private_key: String,
+    /// PEM encoded certificates of the authorities trusted for `tls_client_auth`.
+    pub client_ca: Option<String>,
+}
+
+/// Certificate presented by the client during the TLS handshake.
+#[derive(Clone, Debug)]
+pub(crate) struct ClientCertificate {
+    der: Arc<Vec<u8>>,
+    trusted: bool,
+}
+
+impl ClientCertificate {
+    pub fn new(der: Vec<u8>, authorities: &[Vec<u8>]) -> Self {
+        let trusted = x509_parser::parse_x509_certificate(&der)
+            .map(|(_, cert)| {
+                authorities.iter().any(|authority| {
+                    x509_parser::parse_x509_certificate(authority)
+                        .map(|(_, authority)| {
+                            cert.issuer() == authority.subject()
+                                && cert.verify_signature(Some(authority.public_key())).is_ok()
+                        })
+                        .unwrap_or(false)
+                })
+            })
+            .unwrap_or(false);
+        Self {
+            der: Arc::new(der),
+            trusted,
+        }
+    }
+
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// Whether the certificate was issued by one of the trusted authorities.
+    pub fn is_trusted(&self) -> bool {
+        self.trusted
+    }
+
+    /// Distinguished name of the subject, like `CN=client, O=Example`.
+    pub fn subject(&self) -> Option<String> {
+        x509_parser::parse_x509_certificate(&self.der)
+            .ok()
+            .map(|(_, cert)| cert.subject().to_string())
+    }
+
+    /// `x5t#S256` thumbprint of the certificate, as defined by RFC 8705.
+    pub fn thumbprint(&self) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(sha2::Sha256::digest(self.der()))
+    }
+}
+
+/// Requests a client certificate without requiring it, nor validating its chain,
+/// so that both `tls_client_auth` and `self_signed_tls_client_auth` clients can connect.
+/// The client still has to prove it owns the private key of the certificate.
+#[derive(Debug)]
+struct OptionalClientCertificate(Arc<CryptoProvider>);
+
+impl ClientCertVerifier for OptionalClientCertificate {
+    fn client_auth_mandatory(&self) -> bool {
+        false
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+struct TlsInner {
+    acceptor: TlsAcceptor,
+    authorities: Vec<Vec<u8>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Tls(Arc<TlsInner>);
+
+impl From<Config> for Tls {
+    fn from(value: Config) -> Self {
+        let certificates = CertificateDer::pem_slice_iter(value.certificate.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect("couldn't parse tls certificate");
+        let private_key = PrivateKeyDer::from_pem_slice(value.private_key.as_bytes())
+            .expect("couldn't parse tls private key");
+        let authorities = value
+            .client_ca
+            .iter()
+            .flat_map(|pem| CertificateDer::pem_slice_iter(pem.as_bytes()))
+            .map(|cert| cert.map(|cert| cert.to_vec()))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("couldn't parse tls client authorities");
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .expect("couldn't configure tls protocol versions")
+            .with_client_cert_verifier(Arc::new(OptionalClientCertificate(provider)))
+            .with_single_cert(certificates, private_key)
+            .expect("couldn't configure tls certificate");
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Self(Arc::new(TlsInner {
+            acceptor: TlsAcceptor::from(Arc::new(config)),
+            authorities,
+        }))
+    }
+}
+
+impl Tls {
+    /// Accepts the TLS connections and passes the client certificate, if any,
+    /// to the handlers as a [`ClientCertificate`] extension.
+    pub async fn serve(self, listener: TcpListener, router: axum::Router) {
+        loop {
+            let (stream, address) = match listener.accept().await {
+                Ok(value) => value,
+                Err(err) => {
+                    tracing::error!("unable to accept connection: {err:?}");
+                    continue;
+                }
+            };
+            let tls = self.clone();
+            let router = router.clone();
+            tokio::spawn(async move {
+                let stream = match tls.0.acceptor.accept(stream).await {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        tracing::debug!("tls handshake with {address} failed: {err:?}");
+                        return;
+                    }
+                };
+                let certificate = stream
+                    .get_ref()
+                    .1
+                    .peer_certificates()
+                    .and_then(|chain| chain.first())
+                    .map(|cert| ClientCertificate::new(cert.to_vec(), &tls.0.authorities));
+                let router = match certificate {
+                    Some(certificate) => router.layer(Extension(certificate)),
+                    None => router,
+                };
+                let service = TowerToHyperService::new(router);
+                if let Err(err) = Builder::new(TokioExecutor::new())
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::debug!("connection with {address} failed: {err:?}");
+                }
+            });
+        }
+    }
+}